@@ -0,0 +1,82 @@
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::sync::Arc;
+use std::time::Instant;
+
+use log_hawk::loader::load;
+use log_hawk::log_analyzer::LoadProgress;
+use log_hawk::log_parser::ParserRegistry;
+use log_hawk::timestamp::Zone;
+
+pub const DEFAULT_BENCH_LINES: usize = 10_000_000;
+
+/// `log_hawk --bench [lines]`: generates a Serilog log of `lines` records
+/// (crossing midnight, so the date rollover checks are exercised) and loads
+/// it with one parser thread and with all cores.
+pub fn run(lines: usize) -> io::Result<()> {
+    let path = std::env::temp_dir().join("log_hawk_bench_2024-03-01.log");
+    let path = path.to_string_lossy().into_owned();
+    println!("Генерация {} строк в {}…", lines, path);
+    generate(&path, lines)?;
+    let size = fs::metadata(&path)?.len() as f64 / 1_048_576.0;
+
+    let registry = ParserRegistry::default();
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut thread_counts = vec![1];
+    if cores > 1 {
+        thread_counts.push(cores);
+    }
+
+    for threads in thread_counts {
+        let started = Instant::now();
+        let progress = Arc::new(LoadProgress::default());
+        let result = load(std::slice::from_ref(&path), &registry, Some("serilog"), Zone::Utc, threads, &progress)
+            .expect("benchmark is never cancelled");
+        let elapsed = started.elapsed().as_secs_f64();
+        println!(
+            "потоков: {:>2} | {:.2} с | {:.0} строк/с | {:.1} МБ/с | записей: {} | неудачных входов: {} | подозрительных IP: {} | последняя запись: {}",
+            threads,
+            elapsed,
+            result.report.total_lines as f64 / elapsed,
+            size / elapsed,
+            result.logs.len(),
+            result.analysis.stats.failed_logins,
+            result.suspicious_ips.len(),
+            result.logs.last().map_or_else(|| "—".to_string(), |log| log.timestamp_label(Zone::Utc)),
+        );
+    }
+
+    fs::remove_file(&path)
+}
+
+fn generate(path: &str, lines: usize) -> io::Result<()> {
+    let mut out = BufWriter::with_capacity(1 << 20, fs::File::create(path)?);
+    let users = ["admin", "root", "alice", "bob", "svc_backup", "guest"];
+    let actions = ["Login", "Logout", "ChangePassword", "ResetToken"];
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    for i in 0..lines {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        // 23:00:00 plus 10 ms per record.
+        let millis = (23 * 3600 * 1000 + i as u64 * 10) % (24 * 3600 * 1000);
+        let (h, m, s, ms) = (millis / 3_600_000, millis / 60_000 % 60, millis / 1000 % 60, millis % 1000);
+        let level = if seed.is_multiple_of(50) { "ERR" } else if seed.is_multiple_of(10) { "WRN" } else { "INF" };
+        let ip = if seed.is_multiple_of(5) {
+            format!("2001:db8::{:x}", seed % 4096)
+        } else {
+            format!("10.{}.{}.{}", seed % 4, (seed >> 8) % 256, (seed >> 16) % 256)
+        };
+        writeln!(
+            out,
+            "[{:02}:{:02}:{:02}.{:03} {}] User:{} Status:{} Messages:attempt {} ActionName:{} ClientIp:{}",
+            h, m, s, ms, level,
+            users[(seed >> 24) as usize % users.len()],
+            if seed.is_multiple_of(3) { "False" } else { "True" },
+            i,
+            actions[(seed >> 32) as usize % actions.len()],
+            ip
+        )?;
+    }
+    out.flush()
+}
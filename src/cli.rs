@@ -0,0 +1,340 @@
+use std::collections::HashMap;
+use std::io::{self, BufWriter, Write};
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+
+use log_hawk::loader::{load, LoadResult};
+use log_hawk::log_analyzer::{Attempts, LoadProgress};
+use log_hawk::log_parser::{load_custom_formats, ParserRegistry, CUSTOM_FORMATS_FILE};
+use log_hawk::timestamp::{Timestamp, Zone};
+
+pub const USAGE: &str = "\
+Использование:
+  log_hawk [gui [ФАЙЛ…]]                 открыть окно, сразу загрузив файлы
+  log_hawk analyze [ОПЦИИ] [ФАЙЛ…]       сводка: записи, входы, уровни, активные IP
+  log_hawk suspicious [ОПЦИИ] [ФАЙЛ…]    IP с неудачными входами больше порога
+  log_hawk risk [ОПЦИИ] [ФАЙЛ…]          оценка риска для каждого IP
+  log_hawk --bench [СТРОК]               замер скорости разбора
+
+ФАЙЛ — файл, каталог, маска (*.log) или `-` для стандартного ввода;
+без файлов читается стандартный ввод.
+
+Опции:
+  --json           вывод в JSON вместо таблицы
+  --parser ИМЯ     формат вместо автоопределения (serilog, sshd, syslog, …)
+  --zone ЗОНА      часовой пояс записей без пояса и вывода: local, UTC, Europe/Moscow
+  --formats ФАЙЛ   файл пользовательских форматов (по умолчанию log_hawk_formats.toml)
+  --top N          число строк в таблицах (0 — все)
+
+Код выхода: 0 — готово, 1 — не все входные данные прочитаны, 2 — ошибка в аргументах.";
+
+/// Rows in the top lists of `analyze` unless `--top` says otherwise.
+const DEFAULT_TOP: usize = 10;
+
+/// Reports printed instead of opening the window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Analyze,
+    Suspicious,
+    Risk,
+}
+
+impl Command {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "analyze" => Some(Command::Analyze),
+            "suspicious" => Some(Command::Suspicious),
+            "risk" => Some(Command::Risk),
+            _ => None,
+        }
+    }
+}
+
+struct Options {
+    inputs: Vec<String>,
+    json: bool,
+    parser: Option<String>,
+    zone: Zone,
+    formats: Option<String>,
+    /// `None` for the command's own default, `Some(0)` for everything.
+    top: Option<usize>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = Options { inputs: Vec::new(), json: false, parser: None, zone: Zone::Local, formats: None, top: None };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().cloned().ok_or_else(|| format!("Не указано значение для {}", name));
+            match arg.as_str() {
+                "--json" => options.json = true,
+                "--parser" => options.parser = Some(value(arg)?),
+                "--formats" => options.formats = Some(value(arg)?),
+                "--zone" => {
+                    let zone = value(arg)?;
+                    options.zone = Zone::parse(&zone).ok_or_else(|| format!("Неизвестный часовой пояс: {}", zone))?;
+                }
+                "--top" => {
+                    let top = value(arg)?;
+                    options.top = Some(top.parse().map_err(|_| format!("Некорректное число строк: {}", top))?);
+                }
+                "-" => options.inputs.push(arg.clone()),
+                _ if arg.starts_with('-') => return Err(format!("Неизвестная опция: {}", arg)),
+                _ => options.inputs.push(arg.clone()),
+            }
+        }
+        if options.inputs.is_empty() {
+            options.inputs.push("-".to_string());
+        }
+        Ok(options)
+    }
+
+    fn limit<T>(&self, rows: Vec<T>, default: usize) -> Vec<T> {
+        match self.top.unwrap_or(default) {
+            0 => rows,
+            top => rows.into_iter().take(top).collect(),
+        }
+    }
+}
+
+/// Runs `command` with the rest of the command line and returns the exit code.
+pub fn run(command: Command, args: &[String]) -> i32 {
+    let options = match Options::parse(args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            return 2;
+        }
+    };
+
+    let mut parsers = ParserRegistry::default();
+    let formats = options.formats.as_deref().unwrap_or(CUSTOM_FORMATS_FILE);
+    if options.formats.is_some() || Path::new(formats).exists() {
+        match load_custom_formats(formats) {
+            Ok(custom) => {
+                for parser in custom {
+                    parsers.register(Box::new(parser));
+                }
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                return 2;
+            }
+        }
+    }
+    if let Some(name) = &options.parser {
+        if parsers.get(name).is_none() {
+            let names: Vec<&str> = parsers.parsers().map(|parser| parser.name()).collect();
+            eprintln!("Неизвестный формат: {} (доступны: {})", name, names.join(", "));
+            return 2;
+        }
+    }
+
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let progress = Arc::new(LoadProgress::default());
+    let result = load(&options.inputs, &parsers, options.parser.as_deref(), options.zone, threads, &progress)
+        .expect("command-line loads are never cancelled");
+
+    let mut complete = true;
+    if result.sources.is_empty() {
+        if let Some(error) = &result.report.error {
+            eprintln!("{}", error);
+        }
+        complete = false;
+    }
+    for source in &result.sources {
+        if let Some(error) = &source.report.error {
+            eprintln!("{}", error);
+            complete = false;
+        }
+    }
+
+    let mut out = BufWriter::new(io::stdout().lock());
+    let written = match command {
+        Command::Analyze => write_analysis(&mut out, &result, &options),
+        Command::Suspicious => write_suspicious(&mut out, &result, &options),
+        Command::Risk => write_risk(&mut out, &result, &options),
+    }
+    .and_then(|_| out.flush());
+    match written {
+        // `log_hawk risk | head` is fine.
+        Err(err) if err.kind() != io::ErrorKind::BrokenPipe => {
+            eprintln!("Ошибка вывода: {}", err);
+            1
+        }
+        _ if !complete => 1,
+        _ => 0,
+    }
+}
+
+fn write_analysis(out: &mut impl Write, result: &LoadResult, options: &Options) -> io::Result<()> {
+    let stats = &result.analysis.stats;
+    let timestamps = result.logs.iter().filter_map(|log| log.timestamp);
+    let first = timestamps.clone().min();
+    let last = timestamps.max();
+
+    let mut top_ips: Vec<_> = result.analysis.by_ip.iter().map(|(ip, attempts)| (ip.to_string(), *attempts)).collect();
+    top_ips.sort_by(|a, b| b.1.total.cmp(&a.1.total).then_with(|| a.0.cmp(&b.0)));
+    let top_ips = options.limit(top_ips, DEFAULT_TOP);
+    let mut top_users: Vec<_> = result.analysis.by_user.iter()
+        .filter(|(_, attempts)| attempts.failed > 0)
+        .map(|(user, attempts)| (user.to_string(), *attempts))
+        .collect();
+    top_users.sort_by(|a, b| b.1.failed.cmp(&a.1.failed).then_with(|| a.0.cmp(&b.0)));
+    let top_users = options.limit(top_users, DEFAULT_TOP);
+
+    if options.json {
+        let sources: Vec<Value> = result.sources.iter().map(|source| json!({
+            "path": &*source.path,
+            "parser": source.parser,
+            "detected": source.detection.as_ref().map(|d| json!({ "matched": d.matched, "sampled": d.sampled })),
+            "lines": source.report.total_lines,
+            "records": source.report.total_records,
+            "matched": source.report.matched_lines,
+            "rejected": source.report.rejected_lines,
+            "error": source.report.error,
+        })).collect();
+        let report = json!({
+            "sources": sources,
+            "lines": {
+                "total": result.report.total_lines,
+                "records": result.report.total_records,
+                "matched": result.report.matched_lines,
+                "empty": result.report.empty_lines,
+                "rejected": result.report.rejected_lines,
+                "invalid_utf8": result.report.invalid_utf8_lines,
+            },
+            "entries": result.logs.len(),
+            "events": stats.total_logs,
+            "first": first.map(|ts| ts.to_rfc3339()),
+            "last": last.map(|ts| ts.to_rfc3339()),
+            "successful_logins": stats.successful_logins,
+            "failed_logins": stats.failed_logins,
+            "unique_ips": stats.unique_ips.len(),
+            "levels": stats.level_counts.iter().map(|(level, count)| (level.label().to_string(), json!(count))).collect::<serde_json::Map<_, _>>(),
+            "action_errors": stats.action_errors.iter().map(|(action, count)| (action.to_string(), json!(count))).collect::<serde_json::Map<_, _>>(),
+            "top_ips": top_ips.iter().map(|(ip, attempts)| attempts_json("ip", ip, attempts)).collect::<Vec<_>>(),
+            "top_failed_users": top_users.iter().map(|(user, attempts)| attempts_json("user", user, attempts)).collect::<Vec<_>>(),
+            "suspicious_ips": result.suspicious_ips.len(),
+        });
+        return write_json(out, &report);
+    }
+
+    for source in &result.sources {
+        let parser = source.parser.as_deref().unwrap_or("—");
+        writeln!(
+            out,
+            "📄 {} | {} | строк: {} | записей: {} | распознано: {} | отклонено: {}",
+            source.path, parser, source.report.total_lines, source.report.total_records, source.report.matched_lines, source.report.rejected_lines
+        )?;
+    }
+    writeln!(out)?;
+    writeln!(out, "🗂 Всего записей: {}", result.logs.len())?;
+    if stats.total_logs != result.logs.len() {
+        writeln!(out, "🔁 Событий с учётом повторов: {}", stats.total_logs)?;
+    }
+    if let (Some(first), Some(last)) = (first, last) {
+        writeln!(out, "🕒 Период: {} — {} ({})", time_label(&first, options.zone), time_label(&last, options.zone), options.zone.name())?;
+    }
+    writeln!(out, "🌐 Уникальных IP: {}", stats.unique_ips.len())?;
+    writeln!(out, "✅ Успешных входов: {}", stats.successful_logins)?;
+    writeln!(out, "❌ Неудачных входов: {}", stats.failed_logins)?;
+    let fail_ratio = if stats.total_logs > 0 { stats.failed_logins as f64 / stats.total_logs as f64 } else { 0.0 };
+    writeln!(out, "📉 Доля неудачных попыток: {:.1}%", fail_ratio * 100.0)?;
+    writeln!(out, "🔍 Подозрительных IP: {}", result.suspicious_ips.len())?;
+    if !stats.level_counts.is_empty() {
+        let levels: Vec<String> = stats.level_counts.iter().map(|(level, count)| format!("{} {}", level.label(), count)).collect();
+        writeln!(out, "📶 Уровни: {}", levels.join(", "))?;
+    }
+
+    if !stats.action_errors.is_empty() {
+        writeln!(out, "\n⚠ Ошибки по действиям:")?;
+        let rows = stats.action_errors.iter().map(|(action, count)| vec![action.to_string(), count.to_string()]).collect();
+        write_table(out, &["Действие", "Ошибок"], rows)?;
+    }
+    if !top_ips.is_empty() {
+        writeln!(out, "\n📊 IP по активности:")?;
+        write_table(out, &["IP", "Всего", "Успешных", "Неудачных"], top_ips.iter().map(|(ip, attempts)| attempts_row(ip, attempts)).collect())?;
+    }
+    if !top_users.is_empty() {
+        writeln!(out, "\n👤 Пользователи по неудачным входам:")?;
+        write_table(out, &["Пользователь", "Всего", "Успешных", "Неудачных"], top_users.iter().map(|(user, attempts)| attempts_row(user, attempts)).collect())?;
+    }
+    Ok(())
+}
+
+fn write_suspicious(out: &mut impl Write, result: &LoadResult, options: &Options) -> io::Result<()> {
+    let risks: HashMap<IpAddr, f64> = result.risk_scores.iter().copied().collect();
+    let mut rows: Vec<(IpAddr, Attempts, f64)> = result.suspicious_ips.iter()
+        .map(|ip| (*ip, result.analysis.by_ip[ip], risks.get(ip).copied().unwrap_or(0.0)))
+        .collect();
+    rows.sort_by(|a, b| b.1.failed.cmp(&a.1.failed).then_with(|| a.0.cmp(&b.0)));
+    write_ip_rows(out, options.limit(rows, 0), options)
+}
+
+fn write_risk(out: &mut impl Write, result: &LoadResult, options: &Options) -> io::Result<()> {
+    let rows = result.risk_scores.iter().map(|(ip, risk)| (*ip, result.analysis.by_ip[ip], *risk)).collect();
+    write_ip_rows(out, options.limit(rows, 0), options)
+}
+
+fn write_ip_rows(out: &mut impl Write, rows: Vec<(IpAddr, Attempts, f64)>, options: &Options) -> io::Result<()> {
+    if options.json {
+        let rows: Vec<Value> = rows.iter().map(|(ip, attempts, risk)| {
+            let mut row = attempts_json("ip", &ip.to_string(), attempts);
+            row["risk"] = json!((risk * 1000.0).round() / 1000.0);
+            row
+        }).collect();
+        return write_json(out, &Value::Array(rows));
+    }
+    let rows = rows.iter().map(|(ip, attempts, risk)| {
+        let mut row = attempts_row(&ip.to_string(), attempts);
+        row.push(format!("{:.2}", risk));
+        row
+    }).collect();
+    write_table(out, &["IP", "Всего", "Успешных", "Неудачных", "Риск"], rows)
+}
+
+fn attempts_row(name: &str, attempts: &Attempts) -> Vec<String> {
+    vec![name.to_string(), attempts.total.to_string(), attempts.successful.to_string(), attempts.failed.to_string()]
+}
+
+fn attempts_json(key: &str, name: &str, attempts: &Attempts) -> Value {
+    json!({ key: name, "total": attempts.total, "successful": attempts.successful, "failed": attempts.failed })
+}
+
+fn time_label(ts: &Timestamp, zone: Zone) -> String {
+    zone.format(ts, "%F %T")
+}
+
+fn write_json(out: &mut impl Write, value: &Value) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, value)?;
+    writeln!(out)
+}
+
+/// Columns padded to their widest cell; all but the first are numbers and
+/// aligned right.
+fn write_table(out: &mut impl Write, headers: &[&str], rows: Vec<Vec<String>>) -> io::Result<()> {
+    let mut widths: Vec<usize> = headers.iter().map(|header| header.chars().count()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: &[&str]| {
+        let cells: Vec<String> = cells.iter().zip(&widths).enumerate()
+            .map(|(column, (cell, width))| if column == 0 { format!("{:<width$}", cell) } else { format!("{:>width$}", cell) })
+            .collect();
+        cells.join("  ").trim_end().to_string()
+    };
+    writeln!(out, "{}", line(headers))?;
+    let rule: Vec<String> = widths.iter().map(|width| "─".repeat(*width)).collect();
+    writeln!(out, "{}", rule.join("  "))?;
+    for row in &rows {
+        let cells: Vec<&str> = row.iter().map(String::as_str).collect();
+        writeln!(out, "{}", line(&cells))?;
+    }
+    Ok(())
+}
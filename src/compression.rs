@@ -0,0 +1,42 @@
+use std::io::{BufRead, Read};
+
+use bzip2::bufread::MultiBzDecoder;
+use flate2::bufread::MultiGzDecoder;
+use xz2::bufread::XzDecoder;
+
+/// Compressed containers logrotate and friends leave behind, recognised by
+/// their magic bytes rather than the file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    pub fn detect(header: &[u8]) -> Option<Self> {
+        if header.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if header.starts_with(b"BZh") {
+            Some(Compression::Bzip2)
+        } else if header.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Compression::Xz)
+        } else if header.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// Wraps `reader` in a decoder. Concatenated streams (`cat a.gz b.gz`,
+    /// multi-member `pigz` output) are decoded as one.
+    pub fn decoder<'a, R: BufRead + Send + 'a>(self, reader: R) -> std::io::Result<Box<dyn Read + Send + 'a>> {
+        Ok(match self {
+            Compression::Gzip => Box::new(MultiGzDecoder::new(reader)),
+            Compression::Bzip2 => Box::new(MultiBzDecoder::new(reader)),
+            Compression::Xz => Box::new(XzDecoder::new_multi_decoder(reader)),
+            Compression::Zstd => Box::new(zstd::Decoder::with_buffer(reader)?),
+        })
+    }
+}
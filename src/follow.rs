@@ -0,0 +1,567 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc as std_mpsc, Arc};
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc;
+
+use crate::compression::Compression;
+use crate::loader::SourceReport;
+use crate::log_analyzer::{open_stream, parse_record, LoadProgress, LogEntry, ParseReport};
+use crate::log_parser::{Framing, LineGrouping, LogParser, ParserRegistry, RecordReader, DETECT_SAMPLE_LINES, EVTX_MAGIC};
+use crate::timestamp::{TimeResolver, Zone};
+
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Bytes read from the file per update, so a burst of writes reaches the UI
+/// in pieces.
+const READ_CHUNK: u64 = 4 << 20;
+/// Records per update while a stream is busy; a slower stream sends what it
+/// has every `POLL_INTERVAL`.
+const STREAM_BATCH: usize = 4096;
+
+/// Where a loaded file ends, so following it picks up exactly after the
+/// records already read.
+#[derive(Clone)]
+pub struct TailPosition {
+    pub offset: u64,
+    pub line_number: usize,
+    pub time: TimeResolver,
+    /// The last record read and the line it starts on. Its entry is already
+    /// out, but lines appended later may still continue it.
+    pub record: Option<(usize, Vec<u8>)>,
+}
+
+/// What happened to the followed file, besides growing.
+pub enum FollowEvent {
+    /// The file was truncated in place (`copytruncate`); reading restarts at its beginning.
+    Truncated,
+    /// The path now names a new file; the old one was read to its end first.
+    Rotated,
+    /// The path does not exist, usually for a moment during rotation.
+    Missing,
+    /// A stream was opened and its format settled.
+    Opened(Box<SourceReport>),
+    /// The stream was closed by its writer.
+    Ended,
+    /// Following stopped.
+    Error(String),
+}
+
+/// Records that arrived since the previous update, from a followed file or
+/// the syslog listener.
+#[derive(Default)]
+pub struct LiveUpdate {
+    pub entries: Vec<LogEntry>,
+    /// Entries sent before whose record got more lines since; each replaces
+    /// the entry from the same source and line. Only the message changes,
+    /// as parsers take every other field from the record's first line.
+    pub amended: Vec<LogEntry>,
+    pub report: ParseReport,
+    pub event: Option<FollowEvent>,
+}
+
+impl LiveUpdate {
+    fn error(message: String) -> Self {
+        Self { event: Some(FollowEvent::Error(message)), ..Default::default() }
+    }
+}
+
+/// A plain text log followed like `tail -F`, polled on the tokio blocking pool.
+pub struct FollowTask {
+    stopped: Arc<AtomicBool>,
+    receiver: mpsc::UnboundedReceiver<LiveUpdate>,
+}
+
+impl FollowTask {
+    /// Must be called from inside a tokio runtime.
+    pub fn spawn(path: Arc<str>, start: TailPosition, parsers: ParserRegistry, parser: String) -> Self {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let task_stopped = stopped.clone();
+        tokio::task::spawn_blocking(move || {
+            let Some(parser) = parsers.get(&parser) else {
+                return;
+            };
+            let mut tail = Tail::new(path, start);
+            if let Err(err) = tail.check_format(parser) {
+                let _ = sender.send(LiveUpdate::error(err));
+                return;
+            }
+            while !task_stopped.load(Ordering::Relaxed) {
+                let mut update = LiveUpdate::default();
+                let failed = match tail.poll(parser, &mut update) {
+                    Ok(()) => false,
+                    Err(err) => {
+                        update.event = Some(FollowEvent::Error(format!("Ошибка чтения {}: {}", tail.source, err)));
+                        true
+                    }
+                };
+                let parsed = update.report.total_lines > 0 || update.report.total_records > 0 || !update.amended.is_empty();
+                if parsed || update.event.is_some() {
+                    if sender.send(update).is_err() {
+                        return;
+                    }
+                } else if sender.is_closed() {
+                    return;
+                }
+                if failed {
+                    return;
+                }
+                if !tail.behind {
+                    std::thread::sleep(POLL_INTERVAL);
+                }
+            }
+        });
+        Self { stopped, receiver }
+    }
+
+    /// Reads standard input or a named pipe as it arrives, until the writer
+    /// closes it. Without a `selected` parser the format is detected from
+    /// the first lines, or from what came within `POLL_INTERVAL` if the
+    /// stream is slow. Must be called from inside a tokio runtime.
+    pub fn spawn_stream(path: Arc<str>, parsers: ParserRegistry, selected: Option<String>, zone: Zone) -> Self {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let task_stopped = stopped.clone();
+        tokio::task::spawn_blocking(move || {
+            // Reading blocks until the writer produces more, so it has a thread
+            // of its own and what was parsed meanwhile still goes out in time.
+            let (messages_sender, messages) = std_mpsc::sync_channel(STREAM_BATCH);
+            let (framing_sender, framing) = std_mpsc::channel();
+            let reader_path = path.clone();
+            std::thread::spawn(move || read_stream(&reader_path, messages_sender, framing));
+
+            let mut sample = Vec::new();
+            let mut sample_lines = 0;
+            let mut sampled = selected.is_some();
+            while !sampled {
+                match messages.recv_timeout(POLL_INTERVAL) {
+                    Ok(StreamMessage::Sample(line)) => {
+                        sample.extend_from_slice(&line);
+                        sample_lines += 1;
+                        sampled = sample_lines >= DETECT_SAMPLE_LINES;
+                    }
+                    Ok(StreamMessage::SampleEnd) => sampled = true,
+                    Err(std_mpsc::RecvTimeoutError::Timeout) => sampled = sample_lines > 0,
+                    Ok(StreamMessage::Record(Err(err))) => {
+                        let _ = sender.send(LiveUpdate::error(format!("Не удалось прочитать {}: {}", path, err)));
+                        return;
+                    }
+                    Ok(StreamMessage::Record(_)) | Err(std_mpsc::RecvTimeoutError::Disconnected) => return,
+                }
+                if task_stopped.load(Ordering::Relaxed) {
+                    return;
+                }
+            }
+
+            let detection = if selected.is_none() { parsers.detect(&sample) } else { None };
+            let parser = selected
+                .as_deref()
+                .or(detection.as_ref().map(|d| d.parser.as_str()))
+                .and_then(|name| parsers.get_shared(name));
+            let Some(parser) = parser else {
+                let _ = sender.send(LiveUpdate::error(format!("Не удалось определить формат {}", path)));
+                return;
+            };
+            let source = SourceReport {
+                path: path.clone(),
+                parser: Some(parser.name().to_string()),
+                detection,
+                report: ParseReport::default(),
+                tail: None,
+            };
+            if framing_sender.send(parser.framing()).is_err()
+                || sender.send(LiveUpdate { event: Some(FollowEvent::Opened(Box::new(source))), ..Default::default() }).is_err()
+            {
+                return;
+            }
+
+            let mut time = TimeResolver::for_file(zone, &path);
+            let mut update = LiveUpdate::default();
+            let mut sent = Instant::now();
+            while !task_stopped.load(Ordering::Relaxed) {
+                let ended = match messages.recv_timeout(POLL_INTERVAL) {
+                    Ok(StreamMessage::Record(Ok(Some((line_number, lines, record))))) => {
+                        update.report.total_lines += lines;
+                        let entry = parse_record(&*parser, &path, line_number, &record, &mut time, &mut update.report);
+                        update.entries.extend(entry);
+                        false
+                    }
+                    Ok(StreamMessage::Record(Err(err))) => {
+                        update.event = Some(FollowEvent::Error(format!("Ошибка чтения {}: {}", path, err)));
+                        true
+                    }
+                    Ok(StreamMessage::Record(Ok(None))) | Err(std_mpsc::RecvTimeoutError::Disconnected) => {
+                        update.event = Some(FollowEvent::Ended);
+                        true
+                    }
+                    // Lines read for the sample before the format was settled; they come again as records.
+                    Ok(StreamMessage::Sample(_) | StreamMessage::SampleEnd) | Err(std_mpsc::RecvTimeoutError::Timeout) => false,
+                };
+                let waiting = update.report.total_records;
+                if ended || waiting >= STREAM_BATCH || (waiting > 0 && sent.elapsed() >= POLL_INTERVAL) {
+                    if sender.send(std::mem::take(&mut update)).is_err() {
+                        return;
+                    }
+                    sent = Instant::now();
+                }
+                if ended {
+                    return;
+                }
+            }
+        });
+        Self { stopped, receiver }
+    }
+
+    /// Takes the updates that arrived since the last call.
+    pub fn poll(&mut self) -> Vec<LiveUpdate> {
+        let mut updates = Vec::new();
+        while let Ok(update) = self.receiver.try_recv() {
+            updates.push(update);
+        }
+        updates
+    }
+
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for FollowTask {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+enum StreamMessage {
+    /// A line for format detection.
+    Sample(Vec<u8>),
+    /// The stream ended while sampling.
+    SampleEnd,
+    /// The next record with the line it starts on and the number of lines
+    /// it took, once the framing is known; `None` at the end.
+    Record(io::Result<Option<(usize, usize, Vec<u8>)>>),
+}
+
+/// Sends sample lines until the framing of the detected format arrives,
+/// then the records of the whole stream, the sampled lines included.
+fn read_stream(path: &str, messages: std_mpsc::SyncSender<StreamMessage>, framing: std_mpsc::Receiver<Framing>) {
+    let mut input = match open_stream(path, Arc::new(LoadProgress::default())) {
+        Ok(input) => input,
+        Err(err) => {
+            let _ = messages.send(StreamMessage::Record(Err(err)));
+            return;
+        }
+    };
+    let mut sample = Vec::new();
+    let framing = loop {
+        if let Ok(framing) = framing.try_recv() {
+            break framing;
+        }
+        let start = sample.len();
+        let message = match input.read_until(b'\n', &mut sample) {
+            Ok(0) => StreamMessage::SampleEnd,
+            Ok(_) => StreamMessage::Sample(sample[start..].to_vec()),
+            Err(err) => StreamMessage::Record(Err(err)),
+        };
+        let sampling = matches!(message, StreamMessage::Sample(_));
+        if messages.send(message).is_err() {
+            return;
+        }
+        if !sampling {
+            match framing.recv() {
+                Ok(framing) => break framing,
+                Err(_) => return,
+            }
+        }
+    };
+
+    let mut records = RecordReader::new(io::Cursor::new(sample).chain(input), framing);
+    loop {
+        let mut record = Vec::new();
+        let before = records.line_number();
+        let next = records.next_record(&mut record).map(|read| read.map(|line| (line, records.line_number() - before, record)));
+        let more = matches!(next, Ok(Some(_)));
+        if messages.send(StreamMessage::Record(next)).is_err() || !more {
+            return;
+        }
+    }
+}
+
+struct Tail {
+    source: Arc<str>,
+    file: Option<File>,
+    /// Identity of the open file, to notice when the path is replaced.
+    id: Option<(u64, u64)>,
+    offset: u64,
+    line_number: usize,
+    /// The unterminated end of the file, completed by a later write.
+    pending: Vec<u8>,
+    /// The load stopped inside a line that was still being written; its rest is skipped.
+    skip_line: bool,
+    time: TimeResolver,
+    grouping: Option<Arc<LineGrouping>>,
+    /// The record being grouped and the line it starts on. Its entry is sent
+    /// once the file goes quiet and amended if more lines continue it, until
+    /// a line starts the next record.
+    record: Vec<u8>,
+    record_line: Option<usize>,
+    /// How much of `record` the entry sent for it covers.
+    published: Option<usize>,
+    missing: bool,
+    /// More than one read chunk was waiting, so the next poll comes right away.
+    behind: bool,
+}
+
+impl Tail {
+    fn new(source: Arc<str>, start: TailPosition) -> Self {
+        let (record_line, record) = start.record.map_or((None, Vec::new()), |(line, record)| (Some(line), record));
+        Self {
+            source,
+            file: None,
+            id: None,
+            offset: start.offset,
+            line_number: start.line_number,
+            pending: Vec::new(),
+            skip_line: false,
+            time: start.time,
+            grouping: None,
+            published: record_line.map(|_| record.len()),
+            record,
+            record_line,
+            missing: false,
+            behind: false,
+        }
+    }
+
+    /// Opens the file and refuses what can only be read whole.
+    fn check_format(&mut self, parser: &dyn LogParser) -> Result<(), String> {
+        self.grouping = match parser.framing() {
+            Framing::Line => None,
+            Framing::MultiLine(grouping) => Some(grouping),
+            _ => return Err(format!("Формат «{}» не поддерживает слежение за файлом", parser.display_name())),
+        };
+        if self.grouping.is_none() {
+            self.record.clear();
+            self.record_line = None;
+            self.published = None;
+        }
+        let error = |err: io::Error| format!("Не удалось открыть {}: {}", self.source, err);
+        let mut file = File::open(&*self.source).map_err(error)?;
+        let mut header = [0; 8];
+        let read = file.read(&mut header).map_err(error)?;
+        if header[..read].starts_with(EVTX_MAGIC) || Compression::detect(&header[..read]).is_some() {
+            return Err(format!("Слежение возможно только за несжатым текстовым журналом: {}", self.source));
+        }
+        if self.offset > 0 && file.metadata().map_err(error)?.len() >= self.offset {
+            let mut last = [0; 1];
+            file.seek(SeekFrom::Start(self.offset - 1)).map_err(error)?;
+            file.read_exact(&mut last).map_err(error)?;
+            self.skip_line = last[0] != b'\n';
+        }
+        self.id = file.metadata().ok().as_ref().and_then(file_id);
+        self.file = Some(file);
+        Ok(())
+    }
+
+    fn poll(&mut self, parser: &dyn LogParser, update: &mut LiveUpdate) -> io::Result<()> {
+        let meta = match fs::metadata(&*self.source) {
+            Ok(meta) => Some(meta),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err),
+        };
+        let Some(meta) = meta else {
+            // Whatever was appended before the file was moved away is still ours.
+            self.read(parser, update)?;
+            if !self.missing {
+                self.missing = true;
+                update.event = Some(FollowEvent::Missing);
+            }
+            return Ok(());
+        };
+        self.missing = false;
+
+        if self.file.is_none() || file_id(&meta) != self.id {
+            if self.file.is_some() {
+                self.read(parser, update)?;
+                if !self.behind {
+                    self.flush_pending(parser, update);
+                    self.finish_record(parser, update);
+                    update.event = Some(FollowEvent::Rotated);
+                    self.file = None;
+                }
+            }
+            if self.file.is_none() {
+                self.file = Some(File::open(&*self.source)?);
+                self.id = file_id(&meta);
+                self.restart();
+            }
+        } else if meta.len() < self.offset {
+            self.finish_record(parser, update);
+            update.event = Some(FollowEvent::Truncated);
+            self.restart();
+        }
+        self.read(parser, update)
+    }
+
+    fn restart(&mut self) {
+        self.offset = 0;
+        self.line_number = 0;
+        self.pending.clear();
+        self.skip_line = false;
+    }
+
+    /// Parses the complete lines appended since the last read.
+    fn read(&mut self, parser: &dyn LogParser, update: &mut LiveUpdate) -> io::Result<()> {
+        let Some(file) = &mut self.file else {
+            return Ok(());
+        };
+        file.seek(SeekFrom::Start(self.offset))?;
+        let start = self.pending.len();
+        let read = file.take(READ_CHUNK).read_to_end(&mut self.pending)?;
+        self.offset += read as u64;
+        self.behind = read as u64 == READ_CHUNK;
+        if read == 0 {
+            self.publish_record(parser, update);
+        }
+
+        let Some(end) = self.pending[start..].iter().rposition(|&b| b == b'\n').map(|i| start + i + 1) else {
+            return Ok(());
+        };
+        let complete: Vec<u8> = self.pending.drain(..end).collect();
+        for line in complete[..end - 1].split(|&b| b == b'\n') {
+            if std::mem::take(&mut self.skip_line) {
+                continue;
+            }
+            self.parse(parser, line, update);
+        }
+        Ok(())
+    }
+
+    /// A rotated file will not get the end of its last line any more.
+    fn flush_pending(&mut self, parser: &dyn LogParser, update: &mut LiveUpdate) {
+        let line = std::mem::take(&mut self.pending);
+        if !line.is_empty() && !std::mem::take(&mut self.skip_line) {
+            self.parse(parser, &line, update);
+        }
+    }
+
+    fn parse(&mut self, parser: &dyn LogParser, line: &[u8], update: &mut LiveUpdate) {
+        self.line_number += 1;
+        update.report.total_lines += 1;
+        let Some(grouping) = &self.grouping else {
+            let entry = parse_record(parser, &self.source, self.line_number, line, &mut self.time, &mut update.report);
+            update.entries.extend(entry);
+            return;
+        };
+        if self.record_line.is_some() && grouping.continues(line) {
+            self.record.push(b'\n');
+            self.record.extend_from_slice(line);
+            return;
+        }
+        self.finish_record(parser, update);
+        self.record.extend_from_slice(line);
+        self.record_line = Some(self.line_number);
+    }
+
+    /// Sends the entry for the record being grouped, or amends the one sent
+    /// before if lines were added since; the record stays open.
+    fn publish_record(&mut self, parser: &dyn LogParser, update: &mut LiveUpdate) {
+        let Some(line_number) = self.record_line else {
+            return;
+        };
+        match self.published {
+            Some(published) if published == self.record.len() => {}
+            Some(_) => {
+                // Counted and resolved when it was first sent.
+                let mut time = self.time.clone();
+                let mut report = ParseReport::default();
+                let entry = parse_record(parser, &self.source, line_number, &self.record, &mut time, &mut report);
+                update.amended.extend(entry);
+            }
+            None => {
+                let entry = parse_record(parser, &self.source, line_number, &self.record, &mut self.time, &mut update.report);
+                update.entries.extend(entry);
+            }
+        }
+        self.published = Some(self.record.len());
+    }
+
+    fn finish_record(&mut self, parser: &dyn LogParser, update: &mut LiveUpdate) {
+        self.publish_record(parser, update);
+        self.record_line = None;
+        self.record.clear();
+        self.published = None;
+    }
+}
+
+#[cfg(unix)]
+fn file_id(meta: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+/// Without inode numbers only truncation is noticed, not replacement.
+#[cfg(not(unix))]
+fn file_id(_meta: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+    use crate::loader::load;
+    use crate::log_parser::SerilogParser;
+
+    fn append(path: &std::path::Path, text: &str) {
+        fs::OpenOptions::new().append(true).open(path).unwrap().write_all(text.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn continuation_lines_appended_after_load_amend_the_last_entry() {
+        let path = std::env::temp_dir().join(format!("log_hawk_follow_{}.log", std::process::id()));
+        fs::write(&path, "[10:00:00 INF] Started\n[10:00:01 ERR] Boom\n   at Trace.One()\n").unwrap();
+        let mut parsers = ParserRegistry::new();
+        parsers.register(Box::new(SerilogParser::with_multiline(&SerilogParser::record_start_multiline()).unwrap()));
+        let inputs = [path.to_string_lossy().into_owned()];
+        let progress = Arc::new(LoadProgress::default());
+        let result = load(&inputs, &parsers, Some("serilog"), Zone::Utc, 1, &progress).unwrap();
+        assert_eq!(result.logs.len(), 2);
+        let source = &result.sources[0];
+        let parser = parsers.get("serilog").unwrap();
+        let mut tail = Tail::new(source.path.clone(), source.tail.clone().unwrap());
+        tail.check_format(parser).unwrap();
+
+        let mut update = LiveUpdate::default();
+        tail.poll(parser, &mut update).unwrap();
+        assert!(update.entries.is_empty() && update.amended.is_empty());
+
+        append(&path, "   at Trace.Two()\n");
+        let mut update = LiveUpdate::default();
+        tail.poll(parser, &mut update).unwrap();
+        tail.poll(parser, &mut update).unwrap();
+        assert!(update.entries.is_empty());
+        assert_eq!(update.report.total_lines, 1);
+        assert_eq!(update.report.total_records, 0);
+        let [amended] = update.amended.as_slice() else { panic!("expected one amended entry: {:?}", update.amended) };
+        assert_eq!(amended.line_number, 2);
+        assert_eq!(amended.message, "Boom\n   at Trace.One()\n   at Trace.Two()");
+
+        let mut logs = result.logs;
+        logs.amend(amended.clone());
+        assert_eq!(logs[1].message, amended.message);
+
+        append(&path, "[10:00:02 INF] Next\n");
+        let mut update = LiveUpdate::default();
+        tail.poll(parser, &mut update).unwrap();
+        tail.poll(parser, &mut update).unwrap();
+        assert!(update.amended.is_empty());
+        let [next] = update.entries.as_slice() else { panic!("expected one new entry: {:?}", update.entries) };
+        assert_eq!((next.line_number, next.message.as_str()), (5, "Next"));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
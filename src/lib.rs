@@ -0,0 +1,34 @@
+//! The LogHawk analysis engine, free of any GUI: reading logs (compressed,
+//! rotated, streamed or followed), format detection and parsing, statistics,
+//! suspicious IPs and risk scores. The `log_hawk` binary builds its window
+//! (the `gui` feature) and its command line on top of this crate.
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use log_hawk::{load, LoadProgress, ParserRegistry, Zone};
+//!
+//! let parsers = ParserRegistry::default();
+//! let progress = Arc::new(LoadProgress::default());
+//! let inputs = ["/var/log/auth.log".to_string()];
+//! let result = load(&inputs, &parsers, None, Zone::Local, 1, &progress).expect("never cancelled");
+//! for (ip, risk) in &result.risk_scores {
+//!     println!("{} {:.2}", ip, risk);
+//! }
+//! ```
+
+mod compression;
+pub mod follow;
+pub mod listener;
+pub mod loader;
+pub mod log_analyzer;
+pub mod log_parser;
+pub mod sources;
+pub mod timestamp;
+
+pub use loader::{load, LoadResult, LoadTask, SourceReport};
+pub use log_analyzer::{
+    analyze_logs, Attempts, Level, LoadProgress, LogAnalysis, LogEntry, LogReader, LogStats, LogStore, Outcome, ParseReport,
+};
+pub use log_parser::{Detection, LogParser, ParserRegistry};
+pub use timestamp::{Timestamp, Zone};
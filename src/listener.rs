@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::sync::{mpsc, watch};
+
+use crate::follow::LiveUpdate;
+use crate::log_analyzer::parse_record;
+use crate::log_parser::LogParser;
+use crate::timestamp::{TimeResolver, Zone};
+
+/// Unprivileged, so no root is needed; devices can be pointed at it instead of 514.
+pub const DEFAULT_PORT: u16 = 5514;
+/// Longer messages are dropped, and a TCP sender of one is disconnected.
+const MAX_MESSAGE_LEN: usize = 256 * 1024;
+/// Senders whose time context is kept for UDP; past that it starts over.
+const MAX_UDP_PEERS: usize = 10_000;
+
+/// Receives syslog (RFC 3164 and RFC 5424) over UDP datagrams and TCP
+/// streams, with octet-counting or newline framing (RFC 6587), and parses
+/// every message as it arrives. Dropping it stops all sockets and connections.
+pub struct SyslogListener {
+    /// Label of the listener, used as the source of its entries.
+    pub source: Arc<str>,
+    received: Arc<AtomicUsize>,
+    receiver: mpsc::UnboundedReceiver<LiveUpdate>,
+    /// Never sent to: tasks stop when it is dropped.
+    _shutdown: watch::Sender<()>,
+}
+
+impl SyslogListener {
+    /// Binds the sockets right away so a busy port is reported here. Must be
+    /// called from inside a tokio runtime.
+    pub fn start(port: u16, udp: bool, tcp: bool, parser: Arc<dyn LogParser>, zone: Zone) -> io::Result<Self> {
+        let address = SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), port);
+        let udp_socket = udp.then(|| bind_udp(address)).transpose()?;
+        let tcp_listener = tcp.then(|| bind_tcp(address)).transpose()?;
+
+        let protocols = [udp.then_some("udp"), tcp.then_some("tcp")].into_iter().flatten().collect::<Vec<_>>();
+        let source: Arc<str> = Arc::from(format!("syslog://{}:{}", protocols.join("+"), port));
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let (shutdown, _) = watch::channel(());
+        let received = Arc::new(AtomicUsize::new(0));
+        let receiving = Receiving { source: source.clone(), parser, zone, received: received.clone(), sender };
+
+        if let Some(socket) = udp_socket {
+            tokio::spawn(receive_udp(socket, receiving.clone(), shutdown.subscribe()));
+        }
+        if let Some(listener) = tcp_listener {
+            tokio::spawn(accept_tcp(listener, receiving, shutdown.subscribe()));
+        }
+        Ok(Self { source, received, receiver, _shutdown: shutdown })
+    }
+
+    /// Takes the updates that arrived since the last call.
+    pub fn poll(&mut self) -> Vec<LiveUpdate> {
+        let mut updates = Vec::new();
+        while let Ok(update) = self.receiver.try_recv() {
+            updates.push(update);
+        }
+        updates
+    }
+
+    /// Messages received so far, parsed or not.
+    pub fn received(&self) -> usize {
+        self.received.load(Ordering::Relaxed)
+    }
+}
+
+/// A dual-stack socket where the OS allows it, IPv4 only otherwise.
+fn bind_udp(address: SocketAddr) -> io::Result<UdpSocket> {
+    let socket = std::net::UdpSocket::bind(address)
+        .or_else(|_| std::net::UdpSocket::bind(SocketAddr::from(([0, 0, 0, 0], address.port()))))?;
+    socket.set_nonblocking(true)?;
+    UdpSocket::from_std(socket)
+}
+
+fn bind_tcp(address: SocketAddr) -> io::Result<TcpListener> {
+    let listener = std::net::TcpListener::bind(address)
+        .or_else(|_| std::net::TcpListener::bind(SocketAddr::from(([0, 0, 0, 0], address.port()))))?;
+    listener.set_nonblocking(true)?;
+    TcpListener::from_std(listener)
+}
+
+/// What every receiving task needs to turn messages into entries.
+#[derive(Clone)]
+struct Receiving {
+    source: Arc<str>,
+    parser: Arc<dyn LogParser>,
+    zone: Zone,
+    received: Arc<AtomicUsize>,
+    sender: mpsc::UnboundedSender<LiveUpdate>,
+}
+
+impl Receiving {
+    /// Returns `false` once nobody is listening any more.
+    fn message(&self, raw: &[u8], peer: SocketAddr, time: &mut TimeResolver) -> bool {
+        // The dual-stack socket reports IPv4 senders as `::ffff:a.b.c.d`.
+        let peer = SocketAddr::new(peer.ip().to_canonical(), peer.port());
+        let number = self.received.fetch_add(1, Ordering::Relaxed) + 1;
+        let raw = raw.strip_suffix(b"\n").unwrap_or(raw);
+        let raw = raw.strip_suffix(b"\0").unwrap_or(raw);
+        let mut update = LiveUpdate::default();
+        update.report.total_lines += 1;
+        if let Some(mut entry) = parse_record(&*self.parser, &self.source, number, raw, time, &mut update.report) {
+            entry.attributes.insert("peer", peer.to_string());
+            update.entries.push(entry);
+        }
+        self.sender.send(update).is_ok()
+    }
+}
+
+async fn receive_udp(socket: UdpSocket, receiving: Receiving, mut shutdown: watch::Receiver<()>) {
+    let mut buffer = vec![0; 65_536];
+    let mut times: HashMap<IpAddr, TimeResolver> = HashMap::new();
+    loop {
+        let (length, peer) = tokio::select! {
+            _ = shutdown.changed() => return,
+            received = socket.recv_from(&mut buffer) => match received {
+                Ok(received) => received,
+                // ICMP errors for earlier sends and the like; the socket is still fine.
+                Err(_) => continue,
+            },
+        };
+        let ip = peer.ip().to_canonical();
+        if times.len() >= MAX_UDP_PEERS && !times.contains_key(&ip) {
+            times.clear();
+        }
+        let time = times.entry(ip).or_insert_with(|| TimeResolver::new(receiving.zone));
+        if !receiving.message(&buffer[..length], peer, time) {
+            return;
+        }
+    }
+}
+
+async fn accept_tcp(listener: TcpListener, receiving: Receiving, mut shutdown: watch::Receiver<()>) {
+    loop {
+        let (stream, peer) = tokio::select! {
+            _ = shutdown.changed() => return,
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(_) => continue,
+            },
+        };
+        tokio::spawn(receive_tcp(stream, peer, receiving.clone(), shutdown.clone()));
+    }
+}
+
+async fn receive_tcp(stream: TcpStream, peer: SocketAddr, receiving: Receiving, mut shutdown: watch::Receiver<()>) {
+    let mut reader = BufReader::new(stream);
+    let mut time = TimeResolver::new(receiving.zone);
+    let mut frame = Vec::new();
+    loop {
+        let read = tokio::select! {
+            _ = shutdown.changed() => return,
+            read = read_frame(&mut reader, &mut frame) => read,
+        };
+        match read {
+            Ok(true) => {
+                if !receiving.message(&frame, peer, &mut time) {
+                    return;
+                }
+            }
+            Ok(false) | Err(_) => return,
+        }
+    }
+}
+
+/// Reads one RFC 6587 frame: `LEN SP MSG` when it starts with a digit (a
+/// message itself starts with `<`), otherwise everything up to a newline.
+/// Returns `false` at the end of the stream, and an error for a frame over
+/// `MAX_MESSAGE_LEN`, so a sender cannot fill memory by never ending one.
+async fn read_frame<R: AsyncRead + Unpin>(reader: &mut BufReader<R>, frame: &mut Vec<u8>) -> io::Result<bool> {
+    frame.clear();
+    let first = match reader.fill_buf().await?.first() {
+        Some(&first) => first,
+        None => return Ok(false),
+    };
+    if !first.is_ascii_digit() {
+        let read = (&mut *reader).take(MAX_MESSAGE_LEN as u64 + 1).read_until(b'\n', frame).await?;
+        if frame.len() > MAX_MESSAGE_LEN && frame.last() != Some(&b'\n') {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "слишком длинное сообщение syslog"));
+        }
+        return Ok(read > 0);
+    }
+
+    let mut length = Vec::new();
+    while length.len() <= 8 {
+        let byte = reader.read_u8().await?;
+        if byte == b' ' {
+            let length: usize = std::str::from_utf8(&length).ok().and_then(|n| n.parse().ok()).unwrap_or(usize::MAX);
+            if length > MAX_MESSAGE_LEN {
+                break;
+            }
+            frame.resize(length, 0);
+            reader.read_exact(frame).await?;
+            return Ok(true);
+        }
+        if !byte.is_ascii_digit() {
+            break;
+        }
+        length.push(byte);
+    }
+    Err(io::Error::new(io::ErrorKind::InvalidData, "некорректная длина сообщения syslog"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn octet(message: &str) -> String {
+        format!("{} {}", message.len(), message)
+    }
+
+    #[tokio::test]
+    async fn reads_octet_counted_and_newline_frames() {
+        let first = "<34>1 2003-10-11T22:14:15.003Z host su - ID47 - 'su root' failed\nsecond line";
+        let second = "<13>1 - host app - - - done";
+        let input = format!("{}{}<13>Oct 11 22:14:15 host app: plain\n", octet(first), octet(second));
+        let mut reader = BufReader::new(input.as_bytes());
+        let mut frame = Vec::new();
+
+        assert!(read_frame(&mut reader, &mut frame).await.unwrap());
+        assert_eq!(frame, first.as_bytes());
+        assert!(read_frame(&mut reader, &mut frame).await.unwrap());
+        assert_eq!(frame, second.as_bytes());
+        assert!(read_frame(&mut reader, &mut frame).await.unwrap());
+        assert_eq!(frame, b"<13>Oct 11 22:14:15 host app: plain\n");
+        assert!(!read_frame(&mut reader, &mut frame).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn rejects_bad_and_oversized_frames() {
+        let mut frame = Vec::new();
+        for input in [format!("{} <13>x", MAX_MESSAGE_LEN + 1), "12x <13>x".to_string(), "123456789012 <13>x".to_string()] {
+            let error = read_frame(&mut BufReader::new(input.as_bytes()), &mut frame).await.unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", input);
+        }
+        // A truncated octet-counted frame.
+        let error = read_frame(&mut BufReader::new(&b"20 <13>short"[..]), &mut frame).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+
+        let endless = vec![b'a'; MAX_MESSAGE_LEN * 2];
+        let error = read_frame(&mut BufReader::new(&endless[..]), &mut frame).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(frame.len() <= MAX_MESSAGE_LEN + 1);
+
+        let mut longest = vec![b'a'; MAX_MESSAGE_LEN];
+        longest.push(b'\n');
+        assert!(read_frame(&mut BufReader::new(&longest[..]), &mut frame).await.unwrap());
+        assert_eq!(frame.len(), MAX_MESSAGE_LEN + 1);
+    }
+}
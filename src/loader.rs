@@ -0,0 +1,209 @@
+use std::fs;
+use std::io::BufRead;
+use std::net::IpAddr;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use tokio::sync::oneshot;
+
+use crate::follow::TailPosition;
+use crate::log_analyzer::*;
+use crate::log_parser::{Detection, LogParser, ParserRegistry, DETECT_SAMPLE_LINES};
+use crate::sources::{expand_inputs, is_stream};
+use crate::timestamp::Zone;
+
+/// How one input file was read.
+pub struct SourceReport {
+    pub path: Arc<str>,
+    pub parser: Option<String>,
+    pub detection: Option<Detection>,
+    pub report: ParseReport,
+    /// Where reading stopped, for following the file as it grows.
+    pub tail: Option<TailPosition>,
+}
+
+/// Everything a finished load produces, handed to the UI in one piece.
+pub struct LoadResult {
+    pub logs: LogStore,
+    /// Totals over all sources.
+    pub report: ParseReport,
+    pub sources: Vec<SourceReport>,
+    pub analysis: LogAnalysis,
+    pub suspicious_ips: Vec<IpAddr>,
+    pub risk_scores: Vec<(IpAddr, f64)>,
+}
+
+/// Files being parsed and analyzed on the tokio blocking pool.
+pub struct LoadTask {
+    pub inputs: Vec<String>,
+    pub progress: Arc<LoadProgress>,
+    receiver: oneshot::Receiver<LoadResult>,
+}
+
+impl LoadTask {
+    /// Must be called from inside a tokio runtime. Without a `selected`
+    /// parser, the format of each file is detected separately.
+    pub fn spawn(inputs: Vec<String>, parsers: ParserRegistry, selected: Option<String>, zone: Zone) -> Self {
+        let progress = Arc::new(LoadProgress::default());
+        let (sender, receiver) = oneshot::channel();
+        let task_inputs = inputs.clone();
+        let task_progress = progress.clone();
+        tokio::task::spawn_blocking(move || {
+            let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+            if let Some(result) = load(&task_inputs, &parsers, selected.as_deref(), zone, threads, &task_progress) {
+                let _ = sender.send(result);
+            }
+        });
+        Self { inputs, progress, receiver }
+    }
+
+    /// Returns the result once the task is done; `Some(None)` means it was
+    /// cancelled.
+    pub fn poll(&mut self) -> Option<Option<LoadResult>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(Some(result)),
+            Err(oneshot::error::TryRecvError::Empty) => None,
+            Err(oneshot::error::TryRecvError::Closed) => Some(None),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.progress.cancel();
+    }
+}
+
+/// Parses and analyzes the files named by `inputs` (see `expand_inputs`)
+/// with `threads` parser threads, merging them into one chronologically
+/// ordered store. Returns `None` if `progress` was cancelled.
+pub fn load(
+    inputs: &[String],
+    parsers: &ParserRegistry,
+    selected: Option<&str>,
+    zone: Zone,
+    threads: usize,
+    progress: &Arc<LoadProgress>,
+) -> Option<LoadResult> {
+    let mut logs = LogStore::default();
+    let mut report = ParseReport::default();
+    let mut analysis = LogAnalysis::default();
+    let mut sources = Vec::new();
+
+    let files = match expand_inputs(inputs) {
+        Ok(files) if files.is_empty() => {
+            report.error = Some(format!("Не найдено ни одного файла: {}", inputs.join(", ")));
+            Vec::new()
+        }
+        Ok(files) => files,
+        Err(err) => {
+            report.error = Some(err);
+            Vec::new()
+        }
+    };
+    let sizes: Vec<u64> = files.iter().map(|file| fs::metadata(file).map_or(0, |meta| meta.len())).collect();
+    progress.total_bytes.store(sizes.iter().sum(), Ordering::Relaxed);
+
+    for (file, size) in files.iter().zip(sizes) {
+        let mut source = SourceReport {
+            path: Arc::from(file.as_str()),
+            parser: None,
+            detection: None,
+            report: ParseReport::default(),
+            tail: None,
+        };
+        let mut stream = None;
+        if is_stream(file) {
+            match open_stream(file, progress.clone()).and_then(|input| take_sample(input, DETECT_SAMPLE_LINES)) {
+                Ok((sample, input)) => {
+                    if selected.is_none() {
+                        source.detection = parsers.detect(&sample);
+                    }
+                    stream = Some(input);
+                }
+                Err(err) => source.report.error = Some(format!("Не удалось прочитать {}: {}", file, err)),
+            }
+        } else if selected.is_none() {
+            match read_sample(file, DETECT_SAMPLE_LINES) {
+                Ok(sample) => source.detection = parsers.detect(&sample),
+                Err(err) => source.report.error = Some(format!("Не удалось прочитать {}: {}", file, err)),
+            }
+        }
+        let parser = selected
+            .or(source.detection.as_ref().map(|d| d.parser.as_str()))
+            .and_then(|name| parsers.get(name));
+
+        match parser {
+            Some(parser) if source.report.error.is_none() => {
+                source.parser = Some(parser.name().to_string());
+                let (file_report, file_analysis, tail) = read_file(file, stream, parser, zone, threads, progress, &mut logs)?;
+                source.report = file_report;
+                source.tail = tail;
+                analysis.merge(file_analysis);
+            }
+            _ => {
+                if source.report.error.is_none() {
+                    source.report.error = Some(match selected {
+                        Some(name) => format!("Неизвестный формат «{}» для {}", name, file),
+                        None => format!("Не удалось определить формат {}", file),
+                    });
+                }
+                progress.bytes_read.fetch_add(size, Ordering::Relaxed);
+            }
+        }
+        report.merge(source.report.clone());
+        sources.push(source);
+    }
+
+    if sources.len() > 1 {
+        logs.sort_chronologically();
+    }
+    let suspicious_ips = analysis.suspicious_ips();
+    let risk_scores = analysis.risk_scores();
+    Some(LoadResult { logs, report, sources, analysis, suspicious_ips, risk_scores })
+}
+
+fn read_file(
+    path: &str,
+    stream: Option<Box<dyn BufRead + Send>>,
+    parser: &dyn LogParser,
+    zone: Zone,
+    threads: usize,
+    progress: &Arc<LoadProgress>,
+    logs: &mut LogStore,
+) -> Option<(ParseReport, LogAnalysis, Option<TailPosition>)> {
+    let offset = progress.bytes_read.load(Ordering::Relaxed);
+    // What came through a pipe is gone; there is nothing to follow.
+    let followable = stream.is_none();
+    match stream.map_or_else(|| open_input(path, progress.clone()), Ok) {
+        Ok(input) => {
+            let mut reader = LogReader::new(input, path, parser.framing(), zone, progress.clone());
+            loop {
+                let batch = reader.read_batch(parser, logs, threads);
+                if progress.is_cancelled() {
+                    return None;
+                }
+                match batch {
+                    Ok(true) => {}
+                    Ok(false) => break,
+                    Err(err) => {
+                        reader.report.error = Some(format!("Ошибка чтения {}: {}", path, err));
+                        break;
+                    }
+                }
+            }
+            let tail = (followable && reader.report.error.is_none()).then(|| TailPosition {
+                offset: progress.bytes_read.load(Ordering::Relaxed) - offset,
+                line_number: reader.line_number(),
+                time: reader.time.clone(),
+                record: reader.last_record.take(),
+            });
+            Some((reader.report, reader.analysis, tail))
+        }
+        Err(err) => {
+            let report = ParseReport {
+                error: Some(format!("Не удалось прочитать {}: {}", path, err)),
+                ..Default::default()
+            };
+            Some((report, LogAnalysis::default(), None))
+        }
+    }
+}
@@ -1,122 +1,873 @@
-use regex::Regex;
-use std::collections::{HashMap, HashSet};
+use crate::compression::Compression;
+use chrono::DateTime;
+use crate::log_parser::{EvtxReader, Framing, LogParser, RecordReader, EVTX_MAGIC};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
+use crate::timestamp::{TimeResolver, Timestamp, Zone};
+use std::io::{self, BufRead, BufReader, Read, Seek};
+use std::net::IpAddr;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
 
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Outcome {
+    Success,
+    Failure,
+    #[default]
+    Unknown,
+}
+
+impl Outcome {
+    pub fn from_success(success: bool) -> Self {
+        if success { Outcome::Success } else { Outcome::Failure }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Outcome::Success => "Успех",
+            Outcome::Failure => "Неудача",
+            Outcome::Unknown => "—",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warning,
+    Error,
+    Fatal,
+}
+
+impl Level {
+    pub const ALL: [Level; 6] = [Level::Trace, Level::Debug, Level::Info, Level::Warning, Level::Error, Level::Fatal];
+
+    /// Accepts Serilog short and long names, syslog severities and the
+    /// usual `warn` / `crit` spellings, case-insensitively.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "vrb" | "verbose" | "trace" | "trc" => Some(Level::Trace),
+            "dbg" | "debug" => Some(Level::Debug),
+            "inf" | "info" | "information" | "notice" => Some(Level::Info),
+            "wrn" | "warn" | "warning" => Some(Level::Warning),
+            "err" | "error" => Some(Level::Error),
+            "ftl" | "fatal" | "crit" | "critical" | "alert" | "emerg" | "panic" => Some(Level::Fatal),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Level::Trace => "TRC",
+            Level::Debug => "DBG",
+            Level::Info => "INF",
+            Level::Warning => "WRN",
+            Level::Error => "ERR",
+            Level::Fatal => "FTL",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct LogEntry {
-    pub timestamp: String,
-    pub status: String,
+    pub timestamp: Option<Timestamp>,
+    pub outcome: Outcome,
+    pub user: Option<Arc<str>>,
+    pub ip: Option<IpAddr>,
+    pub level: Option<Level>,
+    pub action: Option<Arc<str>>,
     pub message: String,
-    pub ip: String,
+    pub source_file: Option<Arc<str>>,
+    pub line_number: usize,
+    pub attributes: Attributes,
+    /// Occurrences the line stands for when the syslog daemon folded repeats
+    /// into it (`message repeated N times: [...]`); `None` is once.
+    pub repeated: Option<usize>,
 }
 
-#[derive(Default)]
+/// Extra per-format fields. Kept as a flat list of shared strings: entries
+/// carry a handful of attributes, and `LogStore` dedupes the repeated ones.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Attributes(Vec<(Arc<str>, Arc<str>)>);
+
+impl Attributes {
+    pub fn insert(&mut self, key: impl Into<Arc<str>>, value: impl Into<Arc<str>>) {
+        let (key, value) = (key.into(), value.into());
+        match self.0.iter_mut().find(|(k, _)| *k == key) {
+            Some(slot) => slot.1 = value,
+            None => self.0.push((key, value)),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.iter().find(|(k, _)| &**k == key).map(|(_, v)| &**v)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(k, v)| (&**k, &**v))
+    }
+}
+
+impl LogEntry {
+    /// Stores a client address: IPs go to `ip`, anything else (hostnames,
+    /// `-`) is kept verbatim in the `client` attribute.
+    pub fn set_client(&mut self, value: &str) {
+        match parse_ip(value) {
+            Some((ip, zone)) => {
+                self.ip = Some(ip);
+                if let Some(zone) = zone {
+                    self.attributes.insert("ip_zone", zone);
+                }
+            }
+            None if !value.is_empty() && value != "-" => {
+                self.attributes.insert("client", value);
+            }
+            None => {}
+        }
+    }
+
+    /// Adds the lines that continued the record, such as the stack trace of
+    /// an exception, to the message.
+    pub fn append_continuation(&mut self, continuation: Option<&str>) {
+        if let Some(continuation) = continuation {
+            self.message.push('\n');
+            self.message.push_str(continuation);
+        }
+    }
+
+    /// How many events the entry counts as in statistics.
+    pub fn count(&self) -> usize {
+        self.repeated.unwrap_or(1)
+    }
+
+    pub fn set_user(&mut self, value: &str) {
+        if !value.is_empty() && value != "-" {
+            self.user = Some(value.into());
+        }
+    }
+
+    pub fn timestamp_label(&self, zone: Zone) -> String {
+        self.timestamp
+            .map(|ts| zone.format(&ts, "%Y-%m-%d %H:%M:%S"))
+            .unwrap_or_else(|| "—".to_string())
+    }
+}
+
+/// Parses a client address as logs write it and returns it in canonical
+/// form, so one host is always counted under one key: `[2001:db8::1]:443`,
+/// `1.2.3.4:5555` and `fe80::1%eth0` are accepted (the zone ID is returned
+/// separately), and IPv4-mapped IPv6 addresses become plain IPv4.
+pub fn parse_ip(value: &str) -> Option<(IpAddr, Option<&str>)> {
+    let mut value = value.trim();
+    if let Some(rest) = value.strip_prefix('[') {
+        value = &rest[..rest.find(']')?];
+    } else if value.matches(':').count() == 1 && value.contains('.') {
+        value = value.split(':').next()?;
+    }
+    let (address, zone) = match value.split_once('%') {
+        Some((address, zone)) if !zone.is_empty() => (address, Some(zone)),
+        _ => (value, None),
+    };
+    let ip: IpAddr = address.parse().ok()?;
+    Some((ip.to_canonical(), zone))
+}
+
+/// Matches an IP against the logs tab filter: a full address (in any
+/// notation `parse_ip` accepts) must match exactly, anything else is a
+/// case-insensitive substring of the canonical form.
+pub fn ip_matches_filter(ip: &IpAddr, filter: &str) -> bool {
+    match parse_ip(filter) {
+        Some((filter_ip, _)) => *ip == filter_ip,
+        None => ip.to_string().contains(&filter.trim().to_ascii_lowercase()),
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
 pub struct LogStats {
     pub total_logs: usize,
     pub successful_logins: usize,
     pub failed_logins: usize,
-    pub unique_ips: HashSet<String>,
-}
-
-pub fn read_logs(filename: &str) -> Vec<LogEntry> {
-    let mut entries = Vec::new();
-    if let Ok(contents) = fs::read_to_string(filename) {
-        let re_auth = Regex::new(r"\[(\d{2}:\d{2}:\d{2}) INF\] User:(\w+) Status:(\w+) Messages:(.*?) ActionName:\w+ ClientIp:(\d+\.\d+\.\d+\.\d+)").unwrap();
-
-        for line in contents.lines() {
-            if let Some(caps) = re_auth.captures(line) {
-                entries.push(LogEntry {
-                    timestamp: caps[1].to_string(),
-                    status: format!("Status:{}", &caps[3]),
-                    message: format!("User:{} - Messages:{}", &caps[2], &caps[4]),
-                    ip: caps[5].to_string(),
-                });
+    pub unique_ips: HashSet<IpAddr>,
+    pub level_counts: BTreeMap<Level, usize>,
+    /// Failed logins plus error and fatal events, grouped by action name.
+    pub action_errors: BTreeMap<Arc<str>, usize>,
+}
+
+impl LogStats {
+    pub fn add(&mut self, log: &LogEntry) {
+        let count = log.count();
+        self.total_logs += count;
+        match log.outcome {
+            Outcome::Failure => self.failed_logins += count,
+            Outcome::Success => self.successful_logins += count,
+            Outcome::Unknown => {}
+        }
+        if let Some(ip) = log.ip {
+            self.unique_ips.insert(ip);
+        }
+        if let Some(level) = log.level {
+            *self.level_counts.entry(level).or_insert(0) += count;
+        }
+        let is_error = log.outcome == Outcome::Failure || log.level.is_some_and(|level| level >= Level::Error);
+        if let (true, Some(action)) = (is_error, &log.action) {
+            *self.action_errors.entry(action.clone()).or_insert(0) += count;
+        }
+    }
+}
+
+/// Strings up to this length are interned; longer ones are rarely repeated.
+const MAX_INTERNED_LEN: usize = 64;
+/// Upper bound on distinct interned strings, so high-cardinality values
+/// (request paths, ports) cannot grow the table without limit.
+const MAX_INTERNED: usize = 100_000;
+
+/// Parsed entries of one load. Users, actions and attributes repeat across
+/// millions of lines, so they are stored once and shared between entries.
+#[derive(Default)]
+pub struct LogStore {
+    entries: Vec<LogEntry>,
+    strings: HashSet<Arc<str>>,
+}
+
+impl LogStore {
+    pub fn push(&mut self, entry: LogEntry) {
+        let entry = self.compact(entry);
+        self.entries.push(entry);
+    }
+
+    /// Replaces the entry read from the same source and line, if there is
+    /// one; only recent entries get amended, so the search starts at the end.
+    pub fn amend(&mut self, entry: LogEntry) {
+        let found = self.entries.iter()
+            .rposition(|old| old.line_number == entry.line_number && old.source_file == entry.source_file);
+        if let Some(index) = found {
+            self.entries[index] = self.compact(entry);
+        }
+    }
+
+    fn compact(&mut self, mut entry: LogEntry) -> LogEntry {
+        entry.user = entry.user.map(|user| self.intern(user));
+        entry.action = entry.action.map(|action| self.intern(action));
+        for (key, value) in &mut entry.attributes.0 {
+            *key = self.intern(key.clone());
+            *value = self.intern(value.clone());
+        }
+        entry.attributes.0.shrink_to_fit();
+        entry.message.shrink_to_fit();
+        entry
+    }
+
+    fn intern(&mut self, value: Arc<str>) -> Arc<str> {
+        if value.len() > MAX_INTERNED_LEN {
+            return value;
+        }
+        if let Some(shared) = self.strings.get(&value) {
+            return shared.clone();
+        }
+        if self.strings.len() < MAX_INTERNED {
+            self.strings.insert(value.clone());
+        }
+        value
+    }
+}
+
+impl LogStore {
+    /// Orders entries by time, keeping file order for equal times. Entries
+    /// without a timestamp stay behind the previous entry of their file.
+    pub fn sort_chronologically(&mut self) {
+        let mut keys = Vec::with_capacity(self.entries.len());
+        let mut source = None;
+        let mut last = None;
+        for entry in &self.entries {
+            if entry.source_file.as_ref() != source {
+                source = entry.source_file.as_ref();
+                last = None;
             }
+            last = entry.timestamp.or(last);
+            keys.push(last);
+        }
+        if keys.is_sorted() {
+            return;
         }
+        let mut keyed: Vec<_> = keys.into_iter().zip(std::mem::take(&mut self.entries)).collect();
+        keyed.sort_by_key(|(key, _)| *key);
+        self.entries = keyed.into_iter().map(|(_, entry)| entry).collect();
     }
-    entries
 }
 
-pub fn analyze_logs(logs: &[LogEntry]) -> LogStats {
-    let mut stats = LogStats::default();
+impl Deref for LogStore {
+    type Target = [LogEntry];
 
-    for log in logs {
-        stats.total_logs += 1;
-        if log.status.contains("False") {
-            stats.failed_logins += 1;
-        } else if log.status.contains("True") {
-            stats.successful_logins += 1;
+    fn deref(&self) -> &[LogEntry] {
+        &self.entries
+    }
+}
+
+impl<'a> IntoIterator for &'a LogStore {
+    type Item = &'a LogEntry;
+    type IntoIter = std::slice::Iter<'a, LogEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+pub const MAX_REJECTED_SAMPLES: usize = 100;
+const REJECTED_SAMPLE_LEN: usize = 200;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RejectedLine {
+    pub source: Arc<str>,
+    pub line_number: usize,
+    pub sample: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParseReport {
+    pub total_lines: usize,
+    /// Records parsed; fewer than `total_lines` when a format groups lines.
+    pub total_records: usize,
+    pub matched_lines: usize,
+    pub empty_lines: usize,
+    pub rejected_lines: usize,
+    pub invalid_utf8_lines: usize,
+    pub rejected: Vec<RejectedLine>,
+    pub error: Option<String>,
+}
+
+impl ParseReport {
+    pub fn record_rejected(&mut self, source: &Arc<str>, line_number: usize, line: &str) {
+        self.rejected_lines += 1;
+        if self.rejected.len() < MAX_REJECTED_SAMPLES {
+            let mut sample: String = line.chars().take(REJECTED_SAMPLE_LEN).collect();
+            if sample.len() < line.len() {
+                sample.push('…');
+            }
+            self.rejected.push(RejectedLine { source: source.clone(), line_number, sample });
         }
-        if log.ip != "N/A" {
-            stats.unique_ips.insert(log.ip.clone());
+    }
+
+    pub fn merge(&mut self, other: ParseReport) {
+        self.total_lines += other.total_lines;
+        self.total_records += other.total_records;
+        self.matched_lines += other.matched_lines;
+        self.empty_lines += other.empty_lines;
+        self.rejected_lines += other.rejected_lines;
+        self.invalid_utf8_lines += other.invalid_utf8_lines;
+        let room = MAX_REJECTED_SAMPLES.saturating_sub(self.rejected.len());
+        self.rejected.extend(other.rejected.into_iter().take(room));
+        if self.error.is_none() {
+            self.error = other.error;
         }
     }
 
-    stats
+    pub fn match_rate(&self) -> f64 {
+        let candidates = self.total_records - self.empty_lines;
+        if candidates == 0 {
+            0.0
+        } else {
+            self.matched_lines as f64 / candidates as f64
+        }
+    }
 }
 
-pub fn detect_suspicious_ips(logs: &[LogEntry]) -> Vec<String> {
-    let mut failed_attempts: HashMap<String, usize> = HashMap::new();
+const READ_BUFFER_SIZE: usize = 256 * 1024;
 
-    for log in logs {
-        if log.status.contains("False") {
-            *failed_attempts.entry(log.ip.clone()).or_insert(0) += 1;
+/// Shared between a loading task and the UI: how far the reader got, and
+/// whether the user asked to stop.
+#[derive(Debug, Default)]
+pub struct LoadProgress {
+    pub total_bytes: AtomicU64,
+    pub bytes_read: AtomicU64,
+    pub lines_read: AtomicUsize,
+    pub cancelled: AtomicBool,
+}
+
+impl LoadProgress {
+    pub fn fraction(&self) -> f32 {
+        let total = self.total_bytes.load(Ordering::Relaxed);
+        if total == 0 {
+            0.0
+        } else {
+            (self.bytes_read.load(Ordering::Relaxed) as f64 / total as f64).min(1.0) as f32
         }
     }
 
-    failed_attempts
-        .into_iter()
-        .filter(|&(_, count)| count > 3)
-        .map(|(ip, _)| ip)
-        .collect()
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
 }
 
-pub fn calculate_risk_scores(logs: &[LogEntry]) -> Vec<(String, f64)> {
-    use std::collections::HashMap;
+/// Counts bytes taken from the file itself, before any decoding, so progress
+/// is measured against the size on disk.
+struct CountingReader<R> {
+    inner: R,
+    position: u64,
+    progress: Arc<LoadProgress>,
+}
 
-    let mut ip_entries: HashMap<String, Vec<&LogEntry>> = HashMap::new();
-    for log in logs {
-        ip_entries.entry(log.ip.clone()).or_default().push(log);
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.position += read as u64;
+        self.progress.bytes_read.fetch_add(read as u64, Ordering::Relaxed);
+        Ok(read)
     }
-    
-    let mut raw_scores = vec![];
-    let mut total_counts = vec![];
+}
 
-    for (ip, entries) in &ip_entries {
-        let total = entries.len() as f64;
-        let failed = entries.iter().filter(|l| l.status.contains("False")).count() as f64;
-        total_counts.push(total);
-        raw_scores.push((ip.clone(), total, failed));
+impl<R: Seek> Seek for CountingReader<R> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let position = self.inner.seek(pos)?;
+        if position >= self.position {
+            self.progress.bytes_read.fetch_add(position - self.position, Ordering::Relaxed);
+        } else {
+            self.progress.bytes_read.fetch_sub(self.position - position, Ordering::Relaxed);
+        }
+        self.position = position;
+        Ok(position)
     }
+}
 
-    let min_total = total_counts.iter().cloned().fold(f64::INFINITY, f64::min);
-    let max_total = total_counts.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-    let alpha = 0.7;
-    let beta = 0.3;
-    
-    let mut results = vec![];
+/// Opens a log file for streaming. Compressed files are decompressed and
+/// exported EVTX files decoded to their XML rendering on the fly. Bytes taken
+/// from the file are added to `progress.bytes_read`; setting `total_bytes`
+/// is up to the caller, which may be reading several files.
+pub fn open_input(filename: &str, progress: Arc<LoadProgress>) -> io::Result<Box<dyn BufRead + Send>> {
+    let file = fs::File::open(filename)?;
+    Ok(decode_input(CountingReader { inner: file, position: 0, progress }, None)?.0)
+}
 
-    for (ip, total, failed) in raw_scores {
-        let failed_ratio = if total > 0.0 { failed / total } else { 0.0 };
-        let norm_activity = if (max_total - min_total).abs() < 1e-6 {
-            0.0
+/// Opens standard input (`-`), a named pipe or a device as one stream,
+/// decompressed and decoded like a file.
+pub fn open_stream(path: &str, progress: Arc<LoadProgress>) -> io::Result<Box<dyn BufRead + Send>> {
+    let input: Box<dyn Read + Send> = if path == "-" { Box::new(io::stdin()) } else { Box::new(fs::File::open(path)?) };
+    let reader = BufReader::with_capacity(READ_BUFFER_SIZE, CountingReader { inner: input, position: 0, progress });
+    Ok(decode_stream(reader, None)?.0)
+}
+
+/// Takes the first `max_lines` lines of a stream for format detection. A
+/// stream can only be read once, so they also stay at its front.
+pub fn take_sample(mut reader: Box<dyn BufRead + Send>, max_lines: usize) -> io::Result<(Vec<u8>, Box<dyn BufRead + Send>)> {
+    let mut sample = Vec::new();
+    for _ in 0..max_lines {
+        if reader.read_until(b'\n', &mut sample)? == 0 {
+            break;
+        }
+    }
+    Ok((sample.clone(), Box::new(io::Cursor::new(sample).chain(reader))))
+}
+
+/// Returns the decoded stream and whether it is EVTX, in which case
+/// `max_records` limits the number of events.
+fn decode_input<R: Read + Seek + Send + 'static>(file: R, max_records: Option<usize>) -> io::Result<(Box<dyn BufRead + Send>, bool)> {
+    let mut reader = BufReader::with_capacity(READ_BUFFER_SIZE, file);
+    if reader.fill_buf()?.starts_with(EVTX_MAGIC) {
+        let events = EvtxReader::new(reader, max_records)?;
+        return Ok((Box::new(BufReader::with_capacity(READ_BUFFER_SIZE, events)), true));
+    }
+    decode_stream(reader, max_records)
+}
+
+/// `decode_input` for input that cannot seek. The EVTX parser needs to
+/// seek, so EVTX data coming from a pipe or a decompressor is read into
+/// memory first.
+fn decode_stream<R: BufRead + Send + 'static>(mut reader: R, max_records: Option<usize>) -> io::Result<(Box<dyn BufRead + Send>, bool)> {
+    let mut reader: Box<dyn BufRead + Send> = match Compression::detect(reader.fill_buf()?) {
+        Some(compression) => Box::new(BufReader::with_capacity(READ_BUFFER_SIZE, compression.decoder(reader)?)),
+        None => Box::new(reader),
+    };
+    if reader.fill_buf()?.starts_with(EVTX_MAGIC) {
+        let mut evtx = Vec::new();
+        reader.read_to_end(&mut evtx)?;
+        let events = EvtxReader::new(io::Cursor::new(evtx), max_records)?;
+        return Ok((Box::new(BufReader::with_capacity(READ_BUFFER_SIZE, events)), true));
+    }
+    Ok((reader, false))
+}
+
+/// Records per parse chunk; a batch reads one chunk per thread.
+const CHUNK_RECORDS: usize = 16_384;
+
+/// Raw records of one chunk, copied out of the reader so a worker can parse
+/// them while the next ones are read.
+#[derive(Default)]
+struct RawChunk {
+    data: Vec<u8>,
+    records: Vec<(usize, std::ops::Range<usize>)>,
+}
+
+struct ParsedChunk {
+    entries: Vec<LogEntry>,
+    report: ParseReport,
+    analysis: LogAnalysis,
+    /// The first record that used the time context and the context after it.
+    sync: Option<(usize, TimeResolver)>,
+    time: TimeResolver,
+}
+
+/// Parses a file record by record. Each `read_batch` call handles a bounded
+/// number of records, so callers can report progress or stop between batches.
+pub struct LogReader {
+    records: RecordReader<Box<dyn BufRead + Send>>,
+    source: Arc<str>,
+    /// The time context after the last record read.
+    pub time: TimeResolver,
+    record: Vec<u8>,
+    progress: Arc<LoadProgress>,
+    pub report: ParseReport,
+    /// Statistics over everything read so far, gathered while parsing.
+    pub analysis: LogAnalysis,
+    /// The last raw record read and the line it starts on; lines appended to
+    /// the file later may still continue it.
+    pub last_record: Option<(usize, Vec<u8>)>,
+}
+
+impl LogReader {
+    /// Reads `input`, from `open_input`, `open_stream` or anywhere else;
+    /// `source` names it in the entries and the report.
+    pub fn new(input: Box<dyn BufRead + Send>, source: &str, framing: Framing, zone: Zone, progress: Arc<LoadProgress>) -> Self {
+        Self {
+            records: RecordReader::new(input, framing),
+            source: Arc::from(source),
+            time: TimeResolver::for_file(zone, source),
+            record: Vec::new(),
+            progress,
+            report: ParseReport::default(),
+            analysis: LogAnalysis::default(),
+            last_record: None,
+        }
+    }
+
+    /// Lines read so far; a record may span several.
+    pub fn line_number(&self) -> usize {
+        self.records.line_number()
+    }
+
+    /// Reads one chunk of records per thread and parses the chunks in
+    /// parallel into `store`. Returns `false` once the input is exhausted.
+    ///
+    /// Chunks after the first start from a forked time context. Dates that
+    /// depend on earlier records (midnight or new year rollover) are checked
+    /// against the real context afterwards, and a chunk that guessed wrong is
+    /// parsed again in order.
+    pub fn read_batch(&mut self, parser: &dyn LogParser, store: &mut LogStore, threads: usize) -> io::Result<bool> {
+        let lines_before = self.report.total_lines;
+        let mut chunks = Vec::new();
+        let mut more = true;
+        while more && chunks.len() < threads.max(1) {
+            let mut chunk = RawChunk::default();
+            while chunk.records.len() < CHUNK_RECORDS {
+                let Some(line_number) = self.records.next_record(&mut self.record)? else {
+                    more = false;
+                    break;
+                };
+                let start = chunk.data.len();
+                chunk.data.extend_from_slice(&self.record);
+                chunk.records.push((line_number, start..chunk.data.len()));
+            }
+            if !chunk.records.is_empty() {
+                chunks.push(chunk);
+            }
+        }
+
+        let source = &self.source;
+        let forked = self.time.fork();
+        let parsed: Vec<ParsedChunk> = if chunks.len() > 1 {
+            std::thread::scope(|scope| {
+                let workers: Vec<_> = chunks.iter()
+                    .map(|chunk| {
+                        let time = forked.clone();
+                        scope.spawn(move || parse_chunk(parser, source, chunk, time))
+                    })
+                    .collect();
+                workers.into_iter().map(|worker| worker.join().expect("parser thread panicked")).collect()
+            })
         } else {
-            (total - min_total) / (max_total - min_total)
+            chunks.iter().map(|chunk| parse_chunk(parser, source, chunk, self.time.clone())).collect()
         };
 
-        let risk_score = (alpha * failed_ratio + beta * norm_activity).min(1.0);
-        results.push((ip, risk_score));
+        for (chunk, mut parsed) in chunks.iter().zip(parsed) {
+            if let Some((index, context)) = parsed.sync.take() {
+                let (line_number, range) = chunk.records[index].clone();
+                let mut time = self.time.clone();
+                let mut scratch = ParseReport::default();
+                parse_record(parser, source, line_number, &chunk.data[range], &mut time, &mut scratch);
+                if !time.same_context(&context) {
+                    parsed = parse_chunk(parser, source, chunk, self.time.clone());
+                }
+                self.time = parsed.time;
+            }
+            self.report.merge(parsed.report);
+            self.analysis.merge(parsed.analysis);
+            for entry in parsed.entries {
+                store.push(entry);
+            }
+        }
+        if let Some(chunk) = chunks.last() {
+            let (line_number, range) = chunk.records.last().expect("chunks are never empty").clone();
+            self.last_record = Some((line_number, chunk.data[range].to_vec()));
+        }
+        self.report.total_lines = self.records.line_number();
+        self.progress.lines_read.fetch_add(self.report.total_lines - lines_before, Ordering::Relaxed);
+        Ok(more)
+    }
+}
+
+fn parse_chunk(parser: &dyn LogParser, source: &Arc<str>, chunk: &RawChunk, mut time: TimeResolver) -> ParsedChunk {
+    let mut entries = Vec::with_capacity(chunk.records.len());
+    let mut report = ParseReport::default();
+    let mut sync = None;
+    for (index, (line_number, range)) in chunk.records.iter().enumerate() {
+        let updates = time.updates();
+        let entry = parse_record(parser, source, *line_number, &chunk.data[range.clone()], &mut time, &mut report);
+        if sync.is_none() && time.updates() != updates {
+            sync = Some((index, time.clone()));
+        }
+        entries.extend(entry);
+    }
+    let analysis = analyze_logs(&entries);
+    ParsedChunk { entries, report, analysis, sync, time }
+}
+
+/// Parses one raw record of `source`, counting it in `report`.
+pub fn parse_record(
+    parser: &dyn LogParser,
+    source: &Arc<str>,
+    line_number: usize,
+    raw: &[u8],
+    time: &mut TimeResolver,
+    report: &mut ParseReport,
+) -> Option<LogEntry> {
+    report.total_records += 1;
+    let line = match std::str::from_utf8(raw) {
+        Ok(line) => Cow::Borrowed(line),
+        Err(_) => {
+            report.invalid_utf8_lines += 1;
+            String::from_utf8_lossy(raw)
+        }
+    };
+    let line = line.trim_end_matches('\r');
+    if line.trim().is_empty() {
+        report.empty_lines += 1;
+        return None;
+    }
+
+    match parser.parse_line(line, time) {
+        Some(mut entry) => {
+            report.matched_lines += 1;
+            entry.source_file = Some(source.clone());
+            entry.line_number = line_number;
+            Some(entry)
+        }
+        None => {
+            time.observe_header(line);
+            report.record_rejected(source, line_number, line);
+            None
+        }
+    }
+}
+
+pub fn read_sample(filename: &str, max_lines: usize) -> io::Result<Vec<u8>> {
+    let (mut reader, evtx) = decode_input(fs::File::open(filename)?, Some(max_lines))?;
+    let mut sample = Vec::new();
+    if evtx {
+        reader.read_to_end(&mut sample)?;
+        return Ok(sample);
+    }
+
+    for _ in 0..max_lines {
+        if reader.read_until(b'\n', &mut sample)? == 0 {
+            break;
+        }
+    }
+    Ok(sample)
+}
+
+/// An IP or user with more failed logins than this is reported as suspicious.
+pub const SUSPICIOUS_FAILURES: usize = 3;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Attempts {
+    pub total: usize,
+    pub successful: usize,
+    pub failed: usize,
+}
+
+impl Attempts {
+    fn add(&mut self, outcome: Outcome, count: usize) {
+        self.total += count;
+        match outcome {
+            Outcome::Success => self.successful += count,
+            Outcome::Failure => self.failed += count,
+            Outcome::Unknown => {}
+        }
     }
 
-    results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-    results
+    fn merge(&mut self, other: Attempts) {
+        self.total += other.total;
+        self.successful += other.successful;
+        self.failed += other.failed;
+    }
 }
 
-pub fn risk_to_color(score: f64) -> egui::Color32 {
-    let clamped = score.clamp(0.0, 1.0);
-    let r = (clamped * 255.0) as u8;
-    let g = ((1.0 - clamped) * 255.0) as u8;
-    egui::Color32::from_rgb(r, g, 0)
+/// Everything the tabs derive from the entries, gathered in one pass and
+/// mergeable across chunks.
+#[derive(Debug, Default, PartialEq)]
+pub struct LogAnalysis {
+    pub stats: LogStats,
+    pub by_ip: HashMap<IpAddr, Attempts>,
+    pub by_user: HashMap<Arc<str>, Attempts>,
+    pub actions: BTreeSet<Arc<str>>,
+    /// Attempts per second of the entries' timestamps, for the activity charts.
+    pub timeline: BTreeMap<Timestamp, Attempts>,
 }
 
+impl LogAnalysis {
+    pub fn add(&mut self, log: &LogEntry) {
+        self.stats.add(log);
+        if let Some(ip) = log.ip {
+            self.by_ip.entry(ip).or_default().add(log.outcome, log.count());
+        }
+        if let Some(user) = &log.user {
+            self.by_user.entry(user.clone()).or_default().add(log.outcome, log.count());
+        }
+        if let Some(second) = log.timestamp.and_then(|ts| DateTime::from_timestamp(ts.timestamp(), 0)) {
+            self.timeline.entry(second).or_default().add(log.outcome, log.count());
+        }
+        if let Some(action) = &log.action {
+            if !self.actions.contains(action) {
+                self.actions.insert(action.clone());
+            }
+        }
+    }
+
+    pub fn merge(&mut self, other: LogAnalysis) {
+        let stats = other.stats;
+        self.stats.total_logs += stats.total_logs;
+        self.stats.successful_logins += stats.successful_logins;
+        self.stats.failed_logins += stats.failed_logins;
+        self.stats.unique_ips.extend(stats.unique_ips);
+        for (level, count) in stats.level_counts {
+            *self.stats.level_counts.entry(level).or_insert(0) += count;
+        }
+        for (action, count) in stats.action_errors {
+            *self.stats.action_errors.entry(action).or_insert(0) += count;
+        }
+        for (ip, attempts) in other.by_ip {
+            self.by_ip.entry(ip).or_default().merge(attempts);
+        }
+        for (user, attempts) in other.by_user {
+            self.by_user.entry(user).or_default().merge(attempts);
+        }
+        self.actions.extend(other.actions);
+        for (second, attempts) in other.timeline {
+            self.timeline.entry(second).or_default().merge(attempts);
+        }
+    }
+
+    pub fn suspicious_ips(&self) -> Vec<IpAddr> {
+        self.by_ip.iter()
+            .filter(|(_, attempts)| attempts.failed > SUSPICIOUS_FAILURES)
+            .map(|(ip, _)| *ip)
+            .collect()
+    }
+
+    pub fn risk_scores(&self) -> Vec<(IpAddr, f64)> {
+        let min_total = self.by_ip.values().map(|a| a.total as f64).fold(f64::INFINITY, f64::min);
+        let max_total = self.by_ip.values().map(|a| a.total as f64).fold(f64::NEG_INFINITY, f64::max);
+        let alpha = 0.7;
+        let beta = 0.3;
+
+        let mut results = vec![];
+
+        for (ip, attempts) in &self.by_ip {
+            let total = attempts.total as f64;
+            let failed = attempts.failed as f64;
+            let failed_ratio = if total > 0.0 { failed / total } else { 0.0 };
+            let norm_activity = if (max_total - min_total).abs() < 1e-6 {
+                0.0
+            } else {
+                (total - min_total) / (max_total - min_total)
+            };
+
+            let risk_score = (alpha * failed_ratio + beta * norm_activity).min(1.0);
+            results.push((*ip, risk_score));
+        }
+
+        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        results
+    }
+}
+
+pub fn analyze_logs(logs: &[LogEntry]) -> LogAnalysis {
+    let mut analysis = LogAnalysis::default();
+    for log in logs {
+        analysis.add(log);
+    }
+    analysis
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_parser::SerilogParser;
+    use chrono::{TimeZone, Utc};
+    use std::fmt::Write;
+
+    /// 34 000 Serilog records, ten a second from 23:59:00, so the chunks
+    /// after the first start on the wrong side of midnight.
+    fn crossing_midnight() -> String {
+        let mut log = String::new();
+        for i in 0..34_000u64 {
+            let millis = (86_340_000 + i * 100) % 86_400_000;
+            let (h, m, s, ms) = (millis / 3_600_000, millis / 60_000 % 60, millis / 1000 % 60, millis % 1000);
+            let status = if i % 3 == 0 { "False" } else { "True" };
+            writeln!(
+                log,
+                "[{:02}:{:02}:{:02}.{:03} INF] User:u{} Status:{} Messages:m{} ActionName:Login ClientIp:10.0.{}.1",
+                h, m, s, ms, i % 7, status, i, i % 5
+            )
+            .unwrap();
+            if i % 10_000 == 5 {
+                log.push_str("not a record\n");
+            }
+        }
+        log
+    }
+
+    fn read_all(input: &str, threads: usize) -> (Vec<LogEntry>, LogAnalysis, ParseReport) {
+        let parser = SerilogParser::new();
+        let input: Box<dyn BufRead + Send> = Box::new(io::Cursor::new(input.as_bytes().to_vec()));
+        let mut reader = LogReader::new(input, "app_2024-03-01.log", parser.framing(), Zone::Utc, Arc::new(LoadProgress::default()));
+        let mut store = LogStore::default();
+        while reader.read_batch(&parser, &mut store, threads).unwrap() {}
+        (store.to_vec(), reader.analysis, reader.report)
+    }
+
+    #[test]
+    fn parallel_batches_match_sequential_across_midnight() {
+        let input = crossing_midnight();
+        let (entries, analysis, report) = read_all(&input, 1);
+        assert_eq!(analysis.timeline.keys().next(), Some(&Utc.with_ymd_and_hms(2024, 3, 1, 23, 59, 0).unwrap()));
+        assert_eq!(analysis.timeline.keys().next_back(), Some(&Utc.with_ymd_and_hms(2024, 3, 2, 0, 55, 39).unwrap()));
+        assert_eq!(analysis.timeline.len(), 3400);
+        assert_eq!(report.rejected_lines, 4);
+
+        for threads in [3, 4] {
+            let (parallel_entries, parallel_analysis, parallel_report) = read_all(&input, threads);
+            assert!(parallel_entries == entries, "entries differ with {} threads", threads);
+            assert!(parallel_analysis == analysis, "analysis differs with {} threads", threads);
+            assert_eq!(parallel_report, report, "report differs with {} threads", threads);
+        }
+    }
+}
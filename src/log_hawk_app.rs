@@ -1,23 +1,62 @@
 use egui::Color32;
 use egui::Stroke;
 use rfd::FileDialog;
-use crate::log_analyzer::*;
+use log_hawk::follow::{FollowEvent, FollowTask, LiveUpdate};
+use log_hawk::listener::SyslogListener;
+use log_hawk::loader::{LoadTask, SourceReport};
+use log_hawk::log_analyzer::*;
+use log_hawk::log_parser::*;
+use log_hawk::sources::is_stream;
 use crate::tab::*;
+use log_hawk::timestamp::Zone;
 use egui_plot::*;
 use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 
 #[derive(Default)]
 pub struct LogHawkApp {
-    pub logs: Vec<LogEntry>,
-    pub filtered_logs: Vec<LogEntry>,
-    pub selected_file: Option<String>,
-    pub suspicious_ips: Vec<String>,
-    pub stats: LogStats,
+    pub logs: LogStore,
+    /// Indices into `logs` that pass the logs tab filters.
+    pub filtered_logs: Vec<usize>,
+    /// Files, directories or glob patterns the current data was loaded from.
+    pub selected_inputs: Vec<String>,
+    pub input_pattern: String,
+    pub suspicious_ips: Vec<IpAddr>,
+    pub analysis: LogAnalysis,
     pub filter_ip: String,
-    pub filter_status: String,
+    pub filter_outcome: Option<Outcome>,
+    pub filter_level: Option<Level>,
+    pub filter_action: Option<Arc<str>>,
     pub current_tab: Tab,
-    pub selected_ip: Option<String>,
-    pub risk_scores: Vec<(String, f64)>,
+    pub selected_ip: Option<IpAddr>,
+    /// Indices into `logs` of the entries of `selected_ip`.
+    pub selected_ip_logs: Vec<usize>,
+    pub risk_scores: Vec<(IpAddr, f64)>,
+    pub parsers: ParserRegistry,
+    pub selected_parser: Option<String>,
+    pub sources: Vec<SourceReport>,
+    pub filter_source: Option<Arc<str>>,
+    pub parse_report: Option<ParseReport>,
+    pub json_mapping: JsonFieldMapping,
+    pub access_log_config: AccessLogConfig,
+    pub serilog_multiline: MultilineConfig,
+    pub serilog_multiline_error: Option<String>,
+    pub custom_formats_path: String,
+    pub custom_formats: Vec<String>,
+    pub custom_formats_error: Option<String>,
+    pub source_zone: Zone,
+    pub display_zone: Zone,
+    pub loading: Option<LoadTask>,
+    pub following: Option<FollowTask>,
+    /// The last thing that happened to the followed file.
+    pub follow_status: Option<String>,
+    pub listener: Option<SyslogListener>,
+    pub listen_port: u16,
+    pub listen_udp: bool,
+    pub listen_tcp: bool,
+    pub listen_error: Option<String>,
 }
 
 impl LogHawkApp {
@@ -26,21 +65,130 @@ impl LogHawkApp {
         ui.separator();
 
         ui.horizontal(|ui| {
-            if ui.button("📂 Выбрать файл").clicked() {
-                if let Some(path) = FileDialog::new().add_filter("Text files", &["txt"]).pick_file() {
-                    let path_str = path.display().to_string();
-                    self.selected_file = Some(path_str.clone());
-                    self.logs = read_logs(&path_str);
-                    self.stats = analyze_logs(&self.logs);
-                    self.suspicious_ips = detect_suspicious_ips(&self.logs);
-                    self.risk_scores = calculate_risk_scores(&self.logs);
-                    self.apply_filter();
+            ui.label("🧩 Формат:");
+            let selected_text = self.selected_parser.as_deref()
+                .and_then(|name| self.parsers.get(name))
+                .map_or("Автоопределение", |p| p.display_name());
+            egui::ComboBox::from_id_salt("log_format")
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.selected_parser, None, "Автоопределение");
+                    for parser in self.parsers.parsers() {
+                        ui.selectable_value(&mut self.selected_parser, Some(parser.name().to_string()), parser.display_name());
+                    }
+                });
+
+            if ui.button("📂 Выбрать файлы").clicked() {
+                if let Some(paths) = FileDialog::new()
+                    .add_filter("Log files", &["txt", "log", "json", "gz", "bz2", "xz", "zst"])
+                    .add_filter("Windows Event Log", &["evtx", "xml"])
+                    .add_filter("All files", &["*"])
+                    .pick_files() {
+                    self.load_inputs(paths.iter().map(|path| path.display().to_string()).collect());
                 }
             }
-            if let Some(ref file) = self.selected_file {
-                ui.label(format!("📁 Файл: {}", file));
+            if ui.button("📁 Папка").clicked() {
+                if let Some(path) = FileDialog::new().pick_folder() {
+                    self.load_inputs(vec![path.display().to_string()]);
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("🔎 Шаблон:");
+            ui.add(egui::TextEdit::singleline(&mut self.input_pattern).hint_text("/var/log/auth.log*"));
+            if ui.button("▶ Загрузить").clicked() && !self.input_pattern.trim().is_empty() {
+                self.load_inputs(vec![self.input_pattern.trim().to_string()]);
             }
         });
+        ui.horizontal(|ui| {
+            ui.label("📡 Syslog:");
+            match &self.listener {
+                Some(listener) => {
+                    ui.label(format!("приём на {}, сообщений: {}", listener.source, listener.received()));
+                    if ui.button("⏹ Остановить").clicked() {
+                        self.listener = None;
+                    }
+                }
+                None => {
+                    ui.label("порт");
+                    ui.add(egui::DragValue::new(&mut self.listen_port));
+                    ui.checkbox(&mut self.listen_udp, "UDP");
+                    ui.checkbox(&mut self.listen_tcp, "TCP");
+                    if ui.add_enabled(self.listen_udp || self.listen_tcp, egui::Button::new("▶ Слушать")).clicked() {
+                        self.start_listening();
+                    }
+                }
+            }
+            if let Some(err) = &self.listen_error {
+                ui.colored_label(Color32::RED, err);
+            }
+        });
+        if !self.selected_inputs.is_empty() {
+            ui.label(format!("📁 Источник: {}", self.selected_inputs.join(", ")));
+        }
+
+        match self.sources.as_slice() {
+            [] => {}
+            [source] => self.show_source_format(ui, source),
+            sources => {
+                egui::CollapsingHeader::new(format!("📁 Файлов: {}", sources.len()))
+                    .id_salt("sources")
+                    .show(ui, |ui| {
+                        for source in sources {
+                            ui.horizontal(|ui| {
+                                ui.label(format!("{}:", source.path));
+                                self.show_source_format(ui, source);
+                            });
+                        }
+                    });
+            }
+        }
+        if self.following.is_some() || self.follow_status.is_some() || self.sources.len() == 1 {
+            ui.horizontal(|ui| {
+                match self.sources.as_slice() {
+                    [SourceReport { path, parser: Some(parser), tail: Some(tail), .. }] if self.loading.is_none() => {
+                        let mut follow = self.following.is_some();
+                        if ui.checkbox(&mut follow, "👁 Следить за файлом").changed() {
+                            self.following = follow.then(|| {
+                                FollowTask::spawn(path.clone(), tail.clone(), self.parsers.clone(), parser.clone())
+                            });
+                            self.follow_status = None;
+                        }
+                    }
+                    _ if self.following.is_some() => {
+                        ui.label("📥 Чтение потока…");
+                        if ui.button("⏹ Остановить").clicked() {
+                            self.following = None;
+                        }
+                    }
+                    _ => {}
+                }
+                if let Some(status) = &self.follow_status {
+                    ui.label(status);
+                }
+            });
+        }
+        if let Some(task) = &self.loading {
+            let progress = &task.progress;
+            let mut cancel = false;
+            ui.horizontal(|ui| {
+                ui.label(format!("⏳ Загрузка {}", task.inputs.join(", ")));
+                ui.add(egui::ProgressBar::new(progress.fraction()).show_percentage().desired_width(300.0));
+                ui.label(format!(
+                    "{:.1} / {:.1} МБ, строк: {}",
+                    progress.bytes_read.load(Ordering::Relaxed) as f64 / 1_048_576.0,
+                    progress.total_bytes.load(Ordering::Relaxed) as f64 / 1_048_576.0,
+                    progress.lines_read.load(Ordering::Relaxed)
+                ));
+                cancel = ui.button("✖ Отменить").clicked();
+            });
+            if cancel {
+                self.cancel_loading();
+            }
+        }
+        if let Some(report) = &self.parse_report {
+            Self::show_parse_report(ui, report, self.sources.len() > 1);
+        }
         
         ui.separator();
         ui.label("🔍 Фильтрация логов:");
@@ -50,43 +198,308 @@ impl LogHawkApp {
                 self.apply_filter();
             }
             ui.label("🔹 Статус:");
-            if ui.text_edit_singleline(&mut self.filter_status).changed() {
+            let before = self.filter_outcome;
+            egui::ComboBox::from_id_salt("filter_outcome")
+                .selected_text(self.filter_outcome.map_or("Все", |outcome| outcome.label()))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.filter_outcome, None, "Все");
+                    for outcome in [Outcome::Success, Outcome::Failure, Outcome::Unknown] {
+                        ui.selectable_value(&mut self.filter_outcome, Some(outcome), outcome.label());
+                    }
+                });
+            if self.filter_outcome != before {
                 self.apply_filter();
             }
+
+            ui.label("🔹 Уровень:");
+            let before = self.filter_level;
+            egui::ComboBox::from_id_salt("filter_level")
+                .selected_text(self.filter_level.map_or("Все", |level| level.label()))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.filter_level, None, "Все");
+                    for level in Level::ALL {
+                        ui.selectable_value(&mut self.filter_level, Some(level), level.label());
+                    }
+                });
+            if self.filter_level != before {
+                self.apply_filter();
+            }
+
+            ui.label("🔹 Действие:");
+            let before = self.filter_action.clone();
+            egui::ComboBox::from_id_salt("filter_action")
+                .selected_text(self.filter_action.as_deref().unwrap_or("Все"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.filter_action, None, "Все");
+                    for action in &self.analysis.actions {
+                        ui.selectable_value(&mut self.filter_action, Some(action.clone()), &**action);
+                    }
+                });
+            if self.filter_action != before {
+                self.apply_filter();
+            }
+
+            if self.sources.len() > 1 {
+                ui.label("🔹 Файл:");
+                let before = self.filter_source.clone();
+                egui::ComboBox::from_id_salt("filter_source")
+                    .selected_text(self.filter_source.as_deref().map_or("Все", source_name))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.filter_source, None, "Все");
+                        for source in &self.sources {
+                            ui.selectable_value(&mut self.filter_source, Some(source.path.clone()), source_name(&source.path))
+                                .on_hover_text(&*source.path);
+                        }
+                    });
+                if self.filter_source != before {
+                    self.apply_filter();
+                }
+            }
         });
         
         ui.separator();
         ui.label("📜 Логи:");
-        egui::ScrollArea::vertical().auto_shrink(false).show(ui, |ui| {
-            for log in &self.filtered_logs {
-                let color = match log.status.as_str() {
-                    s if s.contains("False") => egui::Color32::RED,
-                    s if s.contains("True") => egui::Color32::GREEN,
-                    _ => egui::Color32::GRAY,
+        let row_height = ui.text_style_height(&egui::TextStyle::Body);
+        egui::ScrollArea::vertical().auto_shrink(false).stick_to_bottom(self.following.is_some() || self.listener.is_some()).show_rows(ui, row_height, self.filtered_logs.len(), |ui, rows| {
+            for &index in &self.filtered_logs[rows] {
+                let log = &self.logs[index];
+                let color = match log.outcome {
+                    Outcome::Failure => egui::Color32::RED,
+                    Outcome::Success => egui::Color32::GREEN,
+                    Outcome::Unknown => egui::Color32::GRAY,
+                };
+                let label = ui.colored_label(color, Self::entry_line(log, self.display_zone, self.sources.len() > 1));
+                if log.message.contains('\n') {
+                    label.on_hover_text(&log.message);
+                }
+            }
+        });
+    }
+    
+    fn show_source_format(&self, ui: &mut egui::Ui, source: &SourceReport) {
+        match (source.parser.as_deref().and_then(|name| self.parsers.get(name)), &source.detection) {
+            (Some(parser), Some(detection)) => {
+                let color = if detection.match_rate() < 0.5 {
+                    egui::Color32::YELLOW
+                } else {
+                    egui::Color32::GREEN
                 };
                 ui.colored_label(color, format!(
-                    "[{}] {} | {} | {}",
-                    log.timestamp, log.message, log.status, log.ip
+                    "🧩 Определён формат: {} — распознано {} из {} строк ({:.1}%)",
+                    parser.display_name(), detection.matched, detection.sampled, detection.match_rate() * 100.0
                 ));
             }
+            (Some(parser), None) => {
+                ui.label(format!("🧩 Формат: {} — распознано {} из {} записей", parser.display_name(), source.report.matched_lines, source.report.total_records));
+            }
+            (None, _) => {
+                let message = source.report.error.as_deref().unwrap_or("⚠ Формат файла не распознан ни одним из известных парсеров");
+                ui.colored_label(egui::Color32::RED, message);
+            }
+        }
+    }
+
+    fn show_parse_report(ui: &mut egui::Ui, report: &ParseReport, multiple_sources: bool) {
+        if let Some(error) = &report.error {
+            ui.colored_label(egui::Color32::RED, format!("❌ {}", error));
+        }
+        // Only worth showing when lines were grouped into records.
+        let records = if report.total_records != report.total_lines {
+            format!(" | 🧾 Записей: {}", report.total_records)
+        } else {
+            String::new()
+        };
+        ui.label(format!(
+            "📄 Строк: {}{} | ✔ Распознано: {} ({:.1}%) | ✖ Отклонено: {} | Пустых: {}",
+            report.total_lines, records, report.matched_lines, report.match_rate() * 100.0,
+            report.rejected_lines, report.empty_lines
+        ));
+        if report.invalid_utf8_lines > 0 {
+            ui.colored_label(egui::Color32::YELLOW, format!(
+                "⚠ Строк с некорректной UTF-8 кодировкой: {}", report.invalid_utf8_lines
+            ));
+        }
+        if !report.rejected.is_empty() {
+            egui::CollapsingHeader::new(format!("✖ Нераспознанные строки (показано {} из {})", report.rejected.len(), report.rejected_lines))
+                .id_salt("rejected_lines")
+                .show(ui, |ui| {
+                    egui::ScrollArea::vertical().max_height(150.0).show(ui, |ui| {
+                        for rejected in &report.rejected {
+                            if multiple_sources {
+                                ui.monospace(format!("{}:{}: {}", source_name(&rejected.source), rejected.line_number, rejected.sample));
+                            } else {
+                                ui.monospace(format!("{:>6}: {}", rejected.line_number, rejected.sample));
+                            }
+                        }
+                    });
+                });
+        }
+    }
+
+    pub fn show_settings_tab(&mut self, ui: &mut egui::Ui) {
+        ui.heading("⚙ Настройки");
+        ui.separator();
+
+        ui.label("🕒 Часовые пояса:");
+        egui::Grid::new("time_zones").num_columns(2).show(ui, |ui| {
+            ui.label("Пояс источника (для записей без зоны)");
+            let source_zone = self.source_zone;
+            Self::zone_combo(ui, "source_zone", &mut self.source_zone);
+            ui.end_row();
+            ui.label("Пояс отображения");
+            Self::zone_combo(ui, "display_zone", &mut self.display_zone);
+            ui.end_row();
+            // A stream has been consumed and cannot be read again.
+            if self.source_zone != source_zone && !self.selected_inputs.is_empty() && !self.selected_inputs.iter().any(|input| is_stream(input)) {
+                self.load_inputs(self.selected_inputs.clone());
+            }
+        });
+
+        ui.separator();
+
+        ui.label("🧾 Сопоставление полей JSON (ключи через запятую, вложенные — через точку):");
+        Self::edit_key_lists(ui, "json_mapping", self.json_mapping.fields_mut());
+        ui.horizontal(|ui| {
+            if ui.button("✔ Применить").clicked() {
+                for (_, keys) in self.json_mapping.fields_mut() {
+                    keys.retain(|key| !key.is_empty());
+                }
+                self.parsers.register(Box::new(JsonParser::new(self.json_mapping.clone())));
+            }
+            if ui.button("↺ По умолчанию").clicked() {
+                self.json_mapping = JsonFieldMapping::default();
+                self.parsers.register(Box::new(JsonParser::default()));
+            }
+        });
+
+        ui.separator();
+        ui.label("🌐 Access log: 401/403 на путях входа — неудача, 2xx/3xx только с указанными методами (по умолчанию POST) — успех:");
+        Self::edit_key_lists(ui, "access_log_config", self.access_log_config.fields_mut());
+        ui.horizontal(|ui| {
+            if ui.button("✔ Применить").on_hover_text("Применить настройки access log").clicked() {
+                for (_, values) in self.access_log_config.fields_mut() {
+                    values.retain(|value| !value.is_empty());
+                }
+                self.parsers.register(Box::new(AccessLogParser::new(self.access_log_config.clone())));
+            }
+            if ui.button("↺ По умолчанию").on_hover_text("Сбросить настройки access log").clicked() {
+                self.access_log_config = AccessLogConfig::default();
+                self.parsers.register(Box::new(AccessLogParser::default()));
+            }
+        });
+
+        ui.separator();
+        ui.label("📚 Serilog: многострочные записи (исключения и трассировки стека после строки записи; по умолчанию каждая строка — отдельная запись):");
+        ui.horizontal(|ui| {
+            ui.label("Начало записи (regex):");
+            ui.text_edit_singleline(&mut self.serilog_multiline.start_pattern);
+            ui.checkbox(&mut self.serilog_multiline.indented, "Строки с отступом продолжают запись");
+        });
+        ui.horizontal(|ui| {
+            if ui.button("✔ Применить").on_hover_text("Применить группировку строк Serilog").clicked() {
+                match SerilogParser::with_multiline(&self.serilog_multiline) {
+                    Ok(parser) => {
+                        self.parsers.register(Box::new(parser));
+                        self.serilog_multiline_error = None;
+                    }
+                    Err(err) => self.serilog_multiline_error = Some(err),
+                }
+            }
+            if ui.button("📋 По заголовку записи").on_hover_text("Новая запись начинается с [HH:MM:SS").clicked() {
+                self.serilog_multiline = SerilogParser::record_start_multiline();
+            }
+            if ui.button("↺ По умолчанию").on_hover_text("Каждая строка — отдельная запись").clicked() {
+                self.serilog_multiline = MultilineConfig::default();
+                self.serilog_multiline_error = None;
+                self.parsers.register(Box::new(SerilogParser::new()));
+            }
         });
+        if let Some(error) = &self.serilog_multiline_error {
+            ui.colored_label(egui::Color32::RED, format!("❌ {}", error));
+        }
+
+        ui.separator();
+        ui.label("🧪 Пользовательские форматы (TOML, регулярные выражения с именованными группами ts, user, status, ip, msg):");
+        ui.horizontal(|ui| {
+            ui.label("Файл:");
+            ui.text_edit_singleline(&mut self.custom_formats_path);
+            if ui.button("📂").clicked() {
+                if let Some(path) = FileDialog::new().add_filter("TOML", &["toml"]).pick_file() {
+                    self.custom_formats_path = path.display().to_string();
+                    self.load_custom_formats();
+                }
+            }
+            if ui.button("🔄 Загрузить").clicked() {
+                self.load_custom_formats();
+            }
+        });
+        if let Some(error) = &self.custom_formats_error {
+            ui.colored_label(egui::Color32::RED, format!("❌ {}", error));
+        }
+        for name in &self.custom_formats {
+            if let Some(parser) = self.parsers.get(name) {
+                ui.label(format!("• {} ({})", parser.display_name(), name));
+            }
+        }
     }
-    
+
+    pub fn load_custom_formats(&mut self) {
+        match load_custom_formats(&self.custom_formats_path) {
+            Ok(parsers) => {
+                for name in self.custom_formats.drain(..) {
+                    self.parsers.unregister(&name);
+                }
+                for parser in parsers {
+                    self.custom_formats.push(parser.name().to_string());
+                    self.parsers.register(Box::new(parser));
+                }
+                self.custom_formats_error = None;
+            }
+            Err(err) => self.custom_formats_error = Some(err),
+        }
+    }
+
+    fn zone_combo(ui: &mut egui::Ui, id: &str, zone: &mut Zone) {
+        egui::ComboBox::from_id_salt(id)
+            .selected_text(zone.name())
+            .height(300.0)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(zone, Zone::Local, "local");
+                ui.selectable_value(zone, Zone::Utc, "UTC");
+                for tz in chrono_tz::TZ_VARIANTS {
+                    ui.selectable_value(zone, Zone::Named(tz), tz.name());
+                }
+            });
+    }
+
+    fn edit_key_lists<const N: usize>(ui: &mut egui::Ui, id: &str, fields: [(&'static str, &mut Vec<String>); N]) {
+        egui::Grid::new(id).num_columns(2).show(ui, |ui| {
+            for (label, keys) in fields {
+                ui.label(label);
+                let mut text = keys.join(", ");
+                if ui.text_edit_singleline(&mut text).changed() {
+                    *keys = text.split(',').map(|key| key.trim().to_string()).collect();
+                }
+                ui.end_row();
+            }
+        });
+    }
+
     pub fn show_statistics_tab(&self, ui: &mut egui::Ui) {
         ui.heading("📈 Статистика");
         ui.separator();
-        ui.label(format!("Всего логов: {}", self.stats.total_logs));
-        ui.label(format!("Уникальных IP: {}", self.stats.unique_ips.len()));
-        ui.label(format!("✅ Успешные входы: {}", self.stats.successful_logins));
-        ui.label(format!("❌ Неудачные входы: {}", self.stats.failed_logins));
+        ui.label(format!("Всего логов: {}", self.analysis.stats.total_logs));
+        ui.label(format!("Уникальных IP: {}", self.analysis.stats.unique_ips.len()));
+        ui.label(format!("✅ Успешные входы: {}", self.analysis.stats.successful_logins));
+        ui.label(format!("❌ Неудачные входы: {}", self.analysis.stats.failed_logins));
 
         egui::ScrollArea::vertical().auto_shrink(false).show(ui, |ui| {
             ui.vertical(|ui| {
                 ui.label("✅ Успешные vs ❌ Неудачные входы");
                 Plot::new("login_attempts").view_aspect(2.0).show(ui, |plot_ui| {
                     let values = vec![
-                        Bar::new(0.0, self.stats.successful_logins as f64).fill(egui::Color32::GREEN),
-                        Bar::new(1.0, self.stats.failed_logins as f64).fill(egui::Color32::RED),
+                        Bar::new(0.0, self.analysis.stats.successful_logins as f64).fill(egui::Color32::GREEN),
+                        Bar::new(1.0, self.analysis.stats.failed_logins as f64).fill(egui::Color32::RED),
                     ];
                     plot_ui.bar_chart(BarChart::new(values));
                 });
@@ -99,34 +512,37 @@ impl LogHawkApp {
         ui.heading("🔍 Подозрительные IP");
         ui.separator();
         
+        let mut selected = None;
         for ip in &self.suspicious_ips {
-            if ui.button(ip).clicked() {
-                self.selected_ip = Some(ip.clone());
+            if ui.button(ip.to_string()).clicked() {
+                selected = Some(*ip);
             }
         }
+        if selected.is_some() {
+            self.select_ip(selected);
+        }
 
         if let Some(ip) = &self.selected_ip {
             let mut close_requested = false;
 
             egui::Window::new(format!("📊 Аналитика по IP: {}", ip))
                 .show(ui.ctx(), |ui| {
-                    let ip_logs: Vec<_> = self.logs.iter().filter(|log| log.ip == *ip).collect();
-                    let success = ip_logs.iter().filter(|log| log.status.contains("True")).count();
-                    let failed = ip_logs.iter().filter(|log| log.status.contains("False")).count();
+                    let attempts = self.analysis.by_ip.get(ip).copied().unwrap_or_default();
 
-                    ui.label(format!("✅ Успешных попыток: {}", success));
-                    ui.label(format!("❌ Неудачных попыток: {}", failed));
-                    ui.label(format!("📊 Всего записей: {}", ip_logs.len()));
+                    ui.label(format!("✅ Успешных попыток: {}", attempts.successful));
+                    ui.label(format!("❌ Неудачных попыток: {}", attempts.failed));
+                    ui.label(format!("📊 Всего записей: {}", attempts.total));
 
                     if ui.button("📋 Копировать IP").clicked() {
-                        ui.ctx().copy_text(ip.clone());
+                        ui.ctx().copy_text(ip.to_string());
                     }
 
                     ui.separator();
                     ui.label("🕒 Хронология событий:");
-                    egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
-                        for log in ip_logs {
-                            ui.label(format!("[{}] {} - {}", log.timestamp, log.status, log.message));
+                    let row_height = ui.text_style_height(&egui::TextStyle::Body);
+                    egui::ScrollArea::vertical().max_height(200.0).show_rows(ui, row_height, self.selected_ip_logs.len(), |ui, rows| {
+                        for &index in &self.selected_ip_logs[rows] {
+                            ui.label(Self::entry_line(&self.logs[index], self.display_zone, self.sources.len() > 1));
                         }
                     });
 
@@ -137,18 +553,219 @@ impl LogHawkApp {
                 });
 
             if close_requested {
-                self.selected_ip = None;
+                self.select_ip(None);
             }
         }
     }
+
+    /// Opens the details of `ip`, collecting its entries once rather than on
+    /// every repaint.
+    fn select_ip(&mut self, ip: Option<IpAddr>) {
+        self.selected_ip = ip;
+        self.selected_ip_logs = match ip {
+            Some(ip) => self.logs.iter().enumerate().filter(|(_, log)| log.ip == Some(ip)).map(|(index, _)| index).collect(),
+            None => Vec::new(),
+        };
+    }
     
+    /// Starts parsing `inputs` in the background; the current results stay
+    /// on screen until the new ones are ready. A single stream (`-` or a
+    /// named pipe) is instead shown live as it arrives.
+    pub fn load_inputs(&mut self, inputs: Vec<String>) {
+        self.cancel_loading();
+        self.following = None;
+        self.follow_status = None;
+        self.listener = None;
+        self.selected_inputs = inputs.clone();
+        if let [input] = inputs.as_slice() {
+            if is_stream(input) {
+                let task = FollowTask::spawn_stream(Arc::from(input.as_str()), self.parsers.clone(), self.selected_parser.clone(), self.source_zone);
+                self.following = Some(task);
+                self.clear_data(Vec::new());
+                return;
+            }
+        }
+        self.loading = Some(LoadTask::spawn(inputs, self.parsers.clone(), self.selected_parser.clone(), self.source_zone));
+    }
+
+    pub fn cancel_loading(&mut self) {
+        if let Some(task) = self.loading.take() {
+            task.cancel();
+        }
+    }
+
+    /// Swaps in the results of a finished load. Returns `true` while a load
+    /// is still running.
+    pub fn poll_loading(&mut self) -> bool {
+        let Some(task) = &mut self.loading else {
+            return false;
+        };
+        let Some(result) = task.poll() else {
+            return true;
+        };
+        self.loading = None;
+        let Some(result) = result else {
+            return false;
+        };
+
+        self.logs = result.logs;
+        self.parse_report = Some(result.report);
+        self.sources = result.sources;
+        self.analysis = result.analysis;
+        self.suspicious_ips = result.suspicious_ips;
+        self.risk_scores = result.risk_scores;
+        if self.filter_action.as_ref().is_some_and(|action| !self.analysis.actions.contains(action)) {
+            self.filter_action = None;
+        }
+        if self.filter_source.as_ref().is_some_and(|path| !self.sources.iter().any(|source| source.path == *path)) {
+            self.filter_source = None;
+        }
+        self.apply_filter();
+        self.select_ip(self.selected_ip);
+        false
+    }
+
+    /// Starts receiving syslog into a fresh data set, parsed with the selected
+    /// format or the syslog parser.
+    pub fn start_listening(&mut self) {
+        let name = self.selected_parser.clone().unwrap_or_else(|| "syslog".to_string());
+        let Some(parser) = self.parsers.get_shared(&name) else {
+            return;
+        };
+        match SyslogListener::start(self.listen_port, self.listen_udp, self.listen_tcp, parser, self.source_zone) {
+            Ok(listener) => {
+                self.cancel_loading();
+                self.following = None;
+                self.follow_status = None;
+                self.listen_error = None;
+                self.selected_inputs = Vec::new();
+                self.clear_data(vec![SourceReport {
+                    path: listener.source.clone(),
+                    parser: Some(name),
+                    detection: None,
+                    report: ParseReport::default(),
+                    tail: None,
+                }]);
+                self.listener = Some(listener);
+            }
+            Err(err) => self.listen_error = Some(format!("Не удалось открыть порт {}: {}", self.listen_port, err)),
+        }
+    }
+
+    /// Starts an empty data set for live records from `sources`.
+    fn clear_data(&mut self, sources: Vec<SourceReport>) {
+        self.logs = LogStore::default();
+        self.analysis = LogAnalysis::default();
+        self.suspicious_ips = Vec::new();
+        self.risk_scores = Vec::new();
+        self.parse_report = Some(ParseReport::default());
+        self.sources = sources;
+        self.filter_action = None;
+        self.filter_source = None;
+        self.apply_filter();
+        self.select_ip(self.selected_ip);
+    }
+
+    /// Appends what the followed file or the syslog listener received since
+    /// the last frame. Returns `true` while either is running.
+    pub fn poll_live(&mut self) -> bool {
+        let mut updates = Vec::new();
+        if let Some(task) = &mut self.following {
+            updates.extend(task.poll());
+        }
+        if let Some(listener) = &mut self.listener {
+            updates.extend(listener.poll());
+        }
+        self.append_live(updates);
+        self.following.is_some() || self.listener.is_some()
+    }
+
+    /// Adds live records to the data set and updates the analysis
+    /// incrementally instead of re-reading anything.
+    fn append_live(&mut self, updates: Vec<LiveUpdate>) {
+        let mut added = false;
+        for update in updates {
+            for entry in update.entries {
+                self.analysis.add(&entry);
+                if self.matches_filter(&entry) {
+                    self.filtered_logs.push(self.logs.len());
+                }
+                if entry.ip.is_some() && entry.ip == self.selected_ip {
+                    self.selected_ip_logs.push(self.logs.len());
+                }
+                self.logs.push(entry);
+                added = true;
+            }
+            for entry in update.amended {
+                self.logs.amend(entry);
+            }
+            if let Some(report) = &mut self.parse_report {
+                report.merge(update.report.clone());
+            }
+            if let [source] = self.sources.as_mut_slice() {
+                source.report.merge(update.report);
+            }
+            match update.event {
+                Some(FollowEvent::Truncated) => self.follow_status = Some("✂ Файл усечён, чтение с начала".to_string()),
+                Some(FollowEvent::Rotated) => self.follow_status = Some("🔄 Файл заменён при ротации, чтение нового".to_string()),
+                Some(FollowEvent::Missing) => self.follow_status = Some("⏳ Файл отсутствует, ожидание".to_string()),
+                Some(FollowEvent::Opened(source)) => self.sources = vec![*source],
+                Some(FollowEvent::Ended) => {
+                    self.follow_status = Some("⏹ Поток закрыт".to_string());
+                    self.following = None;
+                }
+                Some(FollowEvent::Error(err)) => {
+                    self.follow_status = Some(format!("❌ {}", err));
+                    self.following = None;
+                }
+                None => {}
+            }
+        }
+        if added {
+            self.suspicious_ips = self.analysis.suspicious_ips();
+            self.risk_scores = self.analysis.risk_scores();
+        }
+    }
+
+    /// One row per entry: continuation lines of a multi-line message are
+    /// only counted here.
+    fn entry_line(log: &LogEntry, zone: Zone, show_source: bool) -> String {
+        let mut message = match log.message.split_once('\n') {
+            Some((first, rest)) => format!("{} ↵ +{}", first, rest.lines().count()),
+            None => log.message.clone(),
+        };
+        if let Some(repeated) = log.repeated {
+            message.push_str(&format!(" ×{}", repeated));
+        }
+        let mut line = format!(
+            "[{}{}] {} | {}{} | {} | {}",
+            log.timestamp_label(zone),
+            log.level.map(|level| format!(" {}", level.label())).unwrap_or_default(),
+            log.user.as_deref().unwrap_or("—"),
+            log.action.as_deref().map(|action| format!("{}: ", action)).unwrap_or_default(),
+            message,
+            log.outcome.label(),
+            log.ip.map_or_else(|| "—".to_string(), |ip| ip.to_string())
+        );
+        if let (true, Some(source)) = (show_source, &log.source_file) {
+            line.push_str(&format!(" | 📄 {}:{}", source_name(source), log.line_number));
+        }
+        line
+    }
+
+    fn matches_filter(&self, log: &LogEntry) -> bool {
+        (self.filter_ip.is_empty() || log.ip.is_some_and(|ip| ip_matches_filter(&ip, &self.filter_ip))) &&
+        self.filter_outcome.is_none_or(|outcome| log.outcome == outcome) &&
+        self.filter_level.is_none_or(|level| log.level == Some(level)) &&
+        self.filter_action.as_ref().is_none_or(|action| log.action.as_ref() == Some(action)) &&
+        self.filter_source.as_ref().is_none_or(|source| log.source_file.as_ref() == Some(source))
+    }
+
     pub fn apply_filter(&mut self) {
         self.filtered_logs = self.logs.iter()
-            .filter(|log| {
-                (self.filter_ip.is_empty() || log.ip.contains(&self.filter_ip)) &&
-                (self.filter_status.is_empty() || log.status.to_lowercase().contains(&self.filter_status.to_lowercase()))
-            })
-            .cloned()
+            .enumerate()
+            .filter(|(_, log)| self.matches_filter(log))
+            .map(|(index, _)| index)
             .collect();
     }
 
@@ -163,7 +780,7 @@ impl LogHawkApp {
                 let suspicious_counts: Vec<Bar> = self.suspicious_ips.iter().enumerate().map(|(i, ip)| {
                     Bar::new(i as f64, 1.0)
                         .fill(egui::Color32::DARK_RED)
-                        .name(ip)
+                        .name(ip.to_string())
                 }).collect();
                 
                 if !suspicious_counts.is_empty() {
@@ -178,32 +795,31 @@ impl LogHawkApp {
             ui.vertical(|ui| {
                 ui.label("⏳ Активность логов по времени");
     
-                let mut total_counts: BTreeMap<String, i32> = BTreeMap::new();
-                let mut success_counts: BTreeMap<String, i32> = BTreeMap::new();
-                let mut failed_counts: BTreeMap<String, i32> = BTreeMap::new();
-    
-                for log in &self.logs {
-                    *total_counts.entry(log.timestamp.clone()).or_insert(0) += 1;
-    
-                    if log.status.contains("True") {
-                        *success_counts.entry(log.timestamp.clone()).or_insert(0) += 1;
-                    } else if log.status.contains("False") {
-                        *failed_counts.entry(log.timestamp.clone()).or_insert(0) += 1;
+                let timeline = &self.analysis.timeline;
+                let multi_day = match (timeline.keys().next(), timeline.keys().next_back()) {
+                    (Some(first), Some(last)) => {
+                        self.display_zone.format(first, "%F") != self.display_zone.format(last, "%F")
                     }
-                }
-    
-                let time_labels: Vec<_> = total_counts.keys().cloned().collect();
+                    _ => false,
+                };
+                let label_format = if multi_day { "%d.%m %H:%M:%S" } else { "%H:%M:%S" };
+                // About twenty labels, however many seconds there are.
+                let label_step = (timeline.len() / 20).max(1);
+                let time_labels: Vec<_> = timeline.keys().enumerate()
+                    .filter(|(i, _)| i % label_step == 0)
+                    .map(|(i, ts)| (i, self.display_zone.format(ts, label_format)))
+                    .collect();
     
-                let make_line_points = |counts: &BTreeMap<String, i32>| -> Vec<[f64; 2]> {
-                    counts.iter()
+                let make_line_points = |count: fn(&Attempts) -> usize| -> Vec<[f64; 2]> {
+                    timeline.values()
                         .enumerate()
-                        .map(|(i, (_, count))| [i as f64, *count as f64])
+                        .map(|(i, attempts)| [i as f64, count(attempts) as f64])
                         .collect()
                 };
     
-                let total_points = make_line_points(&total_counts);
-                let success_points = make_line_points(&success_counts);
-                let failed_points = make_line_points(&failed_counts);
+                let total_points = make_line_points(|attempts| attempts.total);
+                let success_points = make_line_points(|attempts| attempts.successful);
+                let failed_points = make_line_points(|attempts| attempts.failed);
     
                 Plot::new("log_activity")
                     .view_aspect(2.0)
@@ -221,8 +837,8 @@ impl LogHawkApp {
                             .name("Неудачные входы")
                             .color(egui::Color32::RED));
     
-                        for (i, label) in time_labels.iter().enumerate() {
-                            plot_ui.text(Text::new([i as f64, 0.0].into(), label.clone()));
+                        for (i, label) in &time_labels {
+                            plot_ui.text(Text::new([*i as f64, 0.0].into(), label.clone()));
                         }
                     });
             });
@@ -231,18 +847,31 @@ impl LogHawkApp {
             ui.vertical(|ui| {
                 ui.label("⚠️ Ошибки по категориям");
             
-                let other_errors = self.stats.total_logs - self.stats.successful_logins - self.stats.failed_logins;
-            
-                let error_types = [("Auth Fail", self.stats.failed_logins), ("Other Errors", other_errors)];
+                let level_count = |level| self.analysis.stats.level_counts.get(&level).copied().unwrap_or(0);
+                let mut error_types = vec![
+                    ("Auth Fail".to_string(), self.analysis.stats.failed_logins, egui::Color32::RED),
+                    ("WRN".to_string(), level_count(Level::Warning), egui::Color32::YELLOW),
+                    ("ERR".to_string(), level_count(Level::Error), egui::Color32::from_rgb(255, 140, 0)),
+                    ("FTL".to_string(), level_count(Level::Fatal), egui::Color32::DARK_RED),
+                ];
+                let mut actions: Vec<_> = self.analysis.stats.action_errors.iter().collect();
+                actions.sort_by_key(|(_, count)| std::cmp::Reverse(**count));
+                for (action, count) in actions.into_iter().take(5) {
+                    error_types.push((action.to_string(), *count, egui::Color32::LIGHT_RED));
+                }
             
-                let bars: Vec<Bar> = error_types.iter().enumerate().map(|(i, (label, count))| {
-                    Bar::new(i as f64, *count as f64).name(label).fill(egui::Color32::RED)
+                let bars: Vec<Bar> = error_types.iter().enumerate().map(|(i, (label, count, color))| {
+                    Bar::new(i as f64, *count as f64).name(label).fill(*color)
                 }).collect();
             
                 Plot::new("error_types")
                     .view_aspect(1.5)
+                    .legend(Legend::default())
                     .show(ui, |plot_ui| {
                         plot_ui.bar_chart(BarChart::new(bars));
+                        for (i, (label, _, _)) in error_types.iter().enumerate() {
+                            plot_ui.text(Text::new([i as f64, 0.0].into(), label.clone()));
+                        }
                     });
             });            
         });
@@ -253,17 +882,16 @@ impl LogHawkApp {
     
         ui.label("🌍 Распределение логов по IP-адресам");
     
-        let mut ip_counts = BTreeMap::new();
-        for log in &self.logs {
-            *ip_counts.entry(log.ip.clone()).or_insert(0) += 1;
-        }
+        let ip_counts: BTreeMap<IpAddr, usize> = self.analysis.by_ip.iter()
+            .map(|(ip, attempts)| (*ip, attempts.total))
+            .collect();
     
         if ip_counts.is_empty() {
             ui.label("Нет данных для отображения.");
             return;
         }
     
-        let total_logs: i32 = ip_counts.values().sum();
+        let total_logs: usize = ip_counts.values().sum();
 
         let colors = [
             Color32::from_rgb(255, 99, 132),
@@ -391,13 +1019,13 @@ impl LogHawkApp {
         ui.heading("📋 Общий обзор логов");
         ui.separator();
         
-        ui.label(format!("🗂 Всего логов: {}", self.stats.total_logs));
-        ui.label(format!("🌐 Уникальных IP: {}", self.stats.unique_ips.len()));
-        ui.label(format!("✅ Успешных входов: {}", self.stats.successful_logins));
-        ui.label(format!("❌ Неудачных входов: {}", self.stats.failed_logins));
+        ui.label(format!("🗂 Всего логов: {}", self.analysis.stats.total_logs));
+        ui.label(format!("🌐 Уникальных IP: {}", self.analysis.stats.unique_ips.len()));
+        ui.label(format!("✅ Успешных входов: {}", self.analysis.stats.successful_logins));
+        ui.label(format!("❌ Неудачных входов: {}", self.analysis.stats.failed_logins));
 
-        let fail_ratio = if self.stats.total_logs > 0 {
-            self.stats.failed_logins as f64 / self.stats.total_logs as f64
+        let fail_ratio = if self.analysis.stats.total_logs > 0 {
+            self.analysis.stats.failed_logins as f64 / self.analysis.stats.total_logs as f64
         } else {
             0.0
         };
@@ -405,35 +1033,30 @@ impl LogHawkApp {
 
         ui.separator();
         
-        use std::collections::HashMap;
-
-        let mut ip_counts: HashMap<String, usize> = HashMap::new();
-        for log in &self.logs {
-            *ip_counts.entry(log.ip.clone()).or_insert(0) += 1;
-        }
-
-        let mut top_ips: Vec<_> = ip_counts.into_iter().collect();
-        top_ips.sort_by(|a, b| b.1.cmp(&a.1));
+        let mut top_ips: Vec<_> = self.analysis.by_ip.iter().map(|(ip, attempts)| (*ip, attempts.total)).collect();
+        top_ips.sort_by_key(|b| std::cmp::Reverse(b.1));
         let top_ips = &top_ips[..top_ips.len().min(5)];
 
         ui.label("📊 Топ-5 IP по активности:");
         for (ip, count) in top_ips {
             ui.label(format!("{} — {} записей", ip, count));
         }
+
+        let mut top_users: Vec<_> = self.analysis.by_user.iter().filter(|(_, attempts)| attempts.failed > 0).collect();
+        top_users.sort_by_key(|(_, attempts)| std::cmp::Reverse(attempts.failed));
+        if !top_users.is_empty() {
+            ui.label("👤 Топ-5 пользователей по неудачным входам:");
+            for (user, attempts) in top_users.into_iter().take(5) {
+                ui.label(format!("{} — {} из {} попыток", user, attempts.failed, attempts.total));
+            }
+        }
         
         ui.separator();
         
-        use std::collections::BTreeMap;
-        let mut counts_by_time: BTreeMap<String, usize> = BTreeMap::new();
-
-        for log in &self.logs {
-            *counts_by_time.entry(log.timestamp.clone()).or_insert(0) += 1;
-        }
-
-        let points: PlotPoints = counts_by_time
-            .iter()
+        let points: PlotPoints = self.analysis.timeline
+            .values()
             .enumerate()
-            .map(|(i, (_, count))| [i as f64, *count as f64])
+            .map(|(i, attempts)| [i as f64, attempts.total as f64])
             .collect();
 
         Plot::new("overview_activity")
@@ -444,3 +1067,14 @@ impl LogHawkApp {
             });
     }
 }
+
+fn risk_to_color(score: f64) -> Color32 {
+    let clamped = score.clamp(0.0, 1.0);
+    let r = (clamped * 255.0) as u8;
+    let g = ((1.0 - clamped) * 255.0) as u8;
+    Color32::from_rgb(r, g, 0)
+}
+
+fn source_name(path: &str) -> &str {
+    std::path::Path::new(path).file_name().and_then(|name| name.to_str()).unwrap_or(path)
+}
@@ -0,0 +1,342 @@
+use crate::log_analyzer::LogEntry;
+use crate::timestamp::{TimeResolver, Zone};
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead};
+use std::sync::Arc;
+
+mod access;
+mod custom;
+mod journal;
+mod json;
+mod serilog;
+mod sshd;
+mod syslog;
+mod windows;
+pub use access::{AccessLogConfig, AccessLogParser};
+pub use custom::{load_custom_formats, CustomFormat, CustomRegexParser, CUSTOM_FORMATS_FILE};
+pub use journal::{JournalExportParser, JournalParser};
+pub use json::{JsonFieldMapping, JsonParser};
+pub use serilog::SerilogParser;
+pub use sshd::SshdParser;
+pub use syslog::SyslogParser;
+pub use windows::{EvtxReader, WindowsEventParser, EVTX_MAGIC};
+
+pub const DETECT_SAMPLE_LINES: usize = 200;
+
+pub trait LogParser: Send + Sync {
+    /// Stable identifier used to pick the parser explicitly.
+    fn name(&self) -> &str;
+    /// Human readable name shown in the file loader.
+    fn display_name(&self) -> &str;
+    /// How the input is cut into records handed to `parse_line`.
+    fn framing(&self) -> Framing {
+        Framing::Line
+    }
+    /// Parses one record; timestamps go through `time`, which fills in the
+    /// date, year or zone the record does not carry itself.
+    fn parse_line(&self, line: &str, time: &mut TimeResolver) -> Option<LogEntry>;
+}
+
+#[derive(Debug, Clone)]
+pub enum Framing {
+    Line,
+    /// Lines grouped into records, such as a log line followed by the stack
+    /// trace of its exception.
+    MultiLine(Arc<LineGrouping>),
+    /// One record per XML element with the given tag, however it is laid out
+    /// across lines; text outside the elements is skipped.
+    XmlElement(&'static str),
+    /// One record per `journalctl -o export` entry, handed over as the JSON
+    /// object `journalctl -o json` would print for it.
+    JournalExport,
+}
+
+/// Decides which lines continue the record before them.
+#[derive(Debug)]
+pub struct LineGrouping {
+    /// Lines that do not match start no record of their own.
+    start: Option<regex::bytes::Regex>,
+    /// Lines starting with a space or a tab start no record of their own.
+    indented: bool,
+}
+
+impl LineGrouping {
+    /// `line` may still carry its `\n` or `\r\n`; patterns never see them.
+    pub fn continues(&self, line: &[u8]) -> bool {
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        (self.indented && matches!(line.first(), Some(b' ' | b'\t')))
+            || self.start.as_ref().is_some_and(|start| !start.is_match(line))
+    }
+}
+
+/// Multi-line grouping as set in the settings or a formats file; with
+/// neither rule set, every line is a record.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MultilineConfig {
+    /// Regex matching the first line of a record.
+    pub start_pattern: String,
+    /// Indented lines continue the previous record.
+    pub indented: bool,
+}
+
+impl MultilineConfig {
+    pub fn framing(&self) -> Result<Framing, String> {
+        let start = match self.start_pattern.trim() {
+            "" => None,
+            pattern => Some(
+                regex::bytes::Regex::new(pattern)
+                    .map_err(|err| format!("Некорректное начало записи: {}", err))?,
+            ),
+        };
+        if start.is_none() && !self.indented {
+            return Ok(Framing::Line);
+        }
+        Ok(Framing::MultiLine(Arc::new(LineGrouping { start, indented: self.indented })))
+    }
+}
+
+/// Splits a record into its first line, which parsers match, and the lines
+/// that continued it, if any.
+pub fn split_continuation(record: &str) -> (&str, Option<&str>) {
+    match record.split_once('\n') {
+        Some((first, rest)) => (first.trim_end_matches('\r'), Some(rest)),
+        None => (record, None),
+    }
+}
+
+const XML_READ_SIZE: usize = 64 * 1024;
+
+/// Cuts a byte stream into records for `framing` without holding more than
+/// the current record (plus one read buffer) in memory.
+pub struct RecordReader<R> {
+    reader: R,
+    framing: Framing,
+    /// Newlines consumed before `pending`.
+    line_number: usize,
+    pending: Vec<u8>,
+}
+
+impl<R: BufRead> RecordReader<R> {
+    pub fn new(reader: R, framing: Framing) -> Self {
+        Self { reader, framing, line_number: 0, pending: Vec::new() }
+    }
+
+    /// Lines taken by the records read so far.
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// Reads the next record into `record` without its line terminator and
+    /// returns the 1-based line it starts on, or `None` at end of input.
+    pub fn next_record(&mut self, record: &mut Vec<u8>) -> io::Result<Option<usize>> {
+        record.clear();
+        match &self.framing {
+            Framing::Line => {
+                if self.reader.read_until(b'\n', record)? == 0 {
+                    return Ok(None);
+                }
+                if record.last() == Some(&b'\n') {
+                    record.pop();
+                }
+                self.line_number += 1;
+                Ok(Some(self.line_number))
+            }
+            Framing::MultiLine(grouping) => {
+                let grouping = grouping.clone();
+                self.next_group(&grouping, record)
+            }
+            Framing::XmlElement(tag) => self.next_element(tag, record),
+            Framing::JournalExport => self.next_journal_entry(record),
+        }
+    }
+
+    /// `pending` holds the line read ahead to see that the record ended.
+    fn next_group(&mut self, grouping: &LineGrouping, record: &mut Vec<u8>) -> io::Result<Option<usize>> {
+        if self.pending.is_empty() && self.reader.read_until(b'\n', &mut self.pending)? == 0 {
+            return Ok(None);
+        }
+        self.line_number += 1;
+        let start = self.line_number;
+        record.append(&mut self.pending);
+        while self.reader.read_until(b'\n', &mut self.pending)? > 0 && grouping.continues(&self.pending) {
+            self.line_number += 1;
+            record.append(&mut self.pending);
+        }
+        if record.last() == Some(&b'\n') {
+            record.pop();
+        }
+        Ok(Some(start))
+    }
+
+    fn next_element(&mut self, tag: &str, record: &mut Vec<u8>) -> io::Result<Option<usize>> {
+        let open = format!("<{}", tag).into_bytes();
+        let close = format!("</{}>", tag).into_bytes();
+        let mut start = None;
+        let mut scanned: usize = 0;
+        loop {
+            if start.is_none() {
+                match find_element_start(&self.pending, &open) {
+                    Some(found) => {
+                        self.line_number += count_lines(&self.pending[..found]);
+                        self.pending.drain(..found);
+                        start = Some(self.line_number + 1);
+                        scanned = 0;
+                    }
+                    None => {
+                        // Keep a tail long enough to hold an opening tag cut by the read.
+                        let keep = self.pending.len().min(open.len());
+                        let drop = self.pending.len() - keep;
+                        self.line_number += count_lines(&self.pending[..drop]);
+                        self.pending.drain(..drop);
+                    }
+                }
+            }
+            if let Some(line) = start {
+                let from = scanned.saturating_sub(close.len());
+                if let Some(end) = find(&self.pending[from..], &close) {
+                    let end = from + end + close.len();
+                    record.extend_from_slice(&self.pending[..end]);
+                    self.line_number += count_lines(&self.pending[..end]);
+                    self.pending.drain(..end);
+                    return Ok(Some(line));
+                }
+                scanned = self.pending.len();
+            }
+
+            let buf = self.reader.fill_buf()?;
+            if buf.is_empty() {
+                // An unterminated element is handed over as-is, so it ends up
+                // in the rejected lines instead of vanishing.
+                return match start {
+                    Some(line) => {
+                        record.append(&mut self.pending);
+                        Ok(Some(line))
+                    }
+                    None => Ok(None),
+                };
+            }
+            let read = buf.len().min(XML_READ_SIZE);
+            self.pending.extend_from_slice(&buf[..read]);
+            self.reader.consume(read);
+        }
+    }
+}
+
+fn count_lines(data: &[u8]) -> usize {
+    data.iter().filter(|&&b| b == b'\n').count()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+fn find_element_start(haystack: &[u8], open: &[u8]) -> Option<usize> {
+    let mut offset = 0;
+    loop {
+        let start = offset + find(&haystack[offset..], open)?;
+        match haystack.get(start + open.len()) {
+            Some(b'>' | b' ' | b'\t' | b'\r' | b'\n' | b'/') => return Some(start),
+            _ => offset = start + open.len(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ParserRegistry {
+    parsers: Vec<Arc<dyn LogParser>>,
+}
+
+impl Default for ParserRegistry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(SerilogParser::new()));
+        registry.register(Box::new(SshdParser::new()));
+        registry.register(Box::new(SyslogParser::new()));
+        // Ahead of the generic JSON parser, which would match journal lines as well.
+        registry.register(Box::new(JournalParser::new()));
+        registry.register(Box::new(JournalExportParser::default()));
+        registry.register(Box::new(JsonParser::default()));
+        registry.register(Box::new(AccessLogParser::default()));
+        registry.register(Box::new(WindowsEventParser::new()));
+        registry
+    }
+}
+
+impl ParserRegistry {
+    pub fn new() -> Self {
+        Self { parsers: Vec::new() }
+    }
+
+    pub fn register(&mut self, parser: Box<dyn LogParser>) {
+        self.parsers.retain(|p| p.name() != parser.name());
+        self.parsers.push(Arc::from(parser));
+    }
+
+    pub fn unregister(&mut self, name: &str) {
+        self.parsers.retain(|p| p.name() != name);
+    }
+
+    pub fn parsers(&self) -> impl Iterator<Item = &dyn LogParser> {
+        self.parsers.iter().map(|p| p.as_ref())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn LogParser> {
+        self.parsers().find(|p| p.name() == name)
+    }
+
+    /// For tasks that outlive a borrow of the registry.
+    pub fn get_shared(&self, name: &str) -> Option<Arc<dyn LogParser>> {
+        self.parsers.iter().find(|p| p.name() == name).cloned()
+    }
+
+    pub fn detect(&self, sample: &[u8]) -> Option<Detection> {
+        let mut best: Option<Detection> = None;
+        for parser in self.parsers() {
+            let mut reader = RecordReader::new(sample, parser.framing());
+            let mut raw = Vec::new();
+            let mut records = Vec::new();
+            while records.len() < DETECT_SAMPLE_LINES {
+                match reader.next_record(&mut raw) {
+                    Ok(Some(_)) => {}
+                    Ok(None) | Err(_) => break,
+                }
+                let record = String::from_utf8_lossy(&raw);
+                if !record.trim().is_empty() {
+                    records.push(record.into_owned());
+                }
+            }
+            let mut time = TimeResolver::new(Zone::Utc);
+            let matched = records
+                .iter()
+                .filter(|record| parser.parse_line(record.trim_end_matches('\r'), &mut time).is_some())
+                .count();
+            if matched > 0 && best.as_ref().is_none_or(|b| matched > b.matched) {
+                best = Some(Detection {
+                    parser: parser.name().to_string(),
+                    matched,
+                    sampled: records.len(),
+                });
+            }
+        }
+        best
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    pub parser: String,
+    pub matched: usize,
+    pub sampled: usize,
+}
+
+impl Detection {
+    pub fn match_rate(&self) -> f64 {
+        if self.sampled == 0 {
+            0.0
+        } else {
+            self.matched as f64 / self.sampled as f64
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::LogParser;
+use crate::log_analyzer::{LogEntry, Outcome};
+use crate::timestamp::TimeResolver;
+
+/// Which requests count as login attempts. A 401/403 on a login path is a
+/// failed login whatever the method (HTTP basic auth), while a 2xx/3xx is
+/// only a success for `success_methods`, so rendering the login form with
+/// `GET /login` is not mistaken for a successful login.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessLogConfig {
+    /// A path and everything below it: `/auth` covers `/auth/token` but not `/authors`.
+    /// `/` and empty entries are ignored rather than matching every request.
+    pub login_paths: Vec<String>,
+    /// Methods whose 2xx/3xx on a login path is a success; `POST` by default.
+    pub success_methods: Vec<String>,
+}
+
+impl AccessLogConfig {
+    pub fn fields_mut(&mut self) -> [(&'static str, &mut Vec<String>); 2] {
+        [
+            ("Пути входа", &mut self.login_paths),
+            ("Методы успеха", &mut self.success_methods),
+        ]
+    }
+
+    fn is_login_path(&self, path: &str) -> bool {
+        let path = path.split(['?', '#']).next().unwrap_or(path);
+        self.login_paths.iter().any(|prefix| {
+            let prefix = prefix.trim().trim_end_matches('/');
+            !prefix.is_empty()
+                && path.strip_prefix(prefix).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+    }
+}
+
+impl Default for AccessLogConfig {
+    fn default() -> Self {
+        let values = |values: &[&str]| values.iter().map(|v| v.to_string()).collect();
+        Self {
+            login_paths: values(&["/login", "/signin", "/admin/login", "/wp-login.php", "/user/login", "/auth", "/api/auth"]),
+            success_methods: values(&["POST"]),
+        }
+    }
+}
+
+pub struct AccessLogParser {
+    config: AccessLogConfig,
+    re_access: Regex,
+}
+
+impl Default for AccessLogParser {
+    fn default() -> Self {
+        Self::new(AccessLogConfig::default())
+    }
+}
+
+impl AccessLogParser {
+    pub fn new(config: AccessLogConfig) -> Self {
+        Self {
+            config,
+            re_access: Regex::new(r#"^(?:(?P<vhost>\S+:\d+) )?(?P<ip>\S+) (?P<ident>\S+) (?P<user>\S+) \[(?P<ts>[^\]]+)\] "(?P<request>(?:[^"\\]|\\.)*)" (?P<status>\d{3}) (?P<bytes>\d+|-)(?: "(?P<referer>(?:[^"\\]|\\.)*)" "(?P<ua>(?:[^"\\]|\\.)*)")?"#).unwrap(),
+        }
+    }
+}
+
+impl LogParser for AccessLogParser {
+    fn name(&self) -> &str {
+        "access"
+    }
+
+    fn display_name(&self) -> &str {
+        "Nginx / Apache access log"
+    }
+
+    fn parse_line(&self, line: &str, time: &mut TimeResolver) -> Option<LogEntry> {
+        let caps = self.re_access.captures(line)?;
+        let mut request = caps["request"].splitn(3, ' ');
+        let method = request.next().unwrap_or_default();
+        let path = request.next().unwrap_or_default();
+        let status: u16 = caps["status"].parse().ok()?;
+
+        let mut entry = LogEntry {
+            timestamp: time.access(&caps["ts"]),
+            message: format!("{} {} → {}", method, path, status),
+            ..Default::default()
+        };
+        entry.set_client(&caps["ip"]);
+        entry.set_user(&caps["user"]);
+        if self.config.is_login_path(path) {
+            entry.attributes.insert("login_endpoint".to_string(), "true".to_string());
+            let success_method = self.config.success_methods.iter().any(|m| m.eq_ignore_ascii_case(method));
+            match status {
+                401 | 403 => entry.outcome = Outcome::Failure,
+                200..=399 if success_method => entry.outcome = Outcome::Success,
+                _ => {}
+            }
+        }
+
+        entry.attributes.insert("method".to_string(), method.to_string());
+        entry.attributes.insert("path".to_string(), path.to_string());
+        entry.attributes.insert("http_status".to_string(), status.to_string());
+        entry.attributes.insert("bytes".to_string(), caps["bytes"].to_string());
+        if let Some(protocol) = request.next() {
+            entry.attributes.insert("protocol".to_string(), protocol.to_string());
+        }
+        if let Some(vhost) = caps.name("vhost") {
+            entry.attributes.insert("vhost".to_string(), vhost.as_str().to_string());
+        }
+        for (group, key) in [("referer", "referer"), ("ua", "user_agent")] {
+            if let Some(value) = caps.name(group).filter(|v| v.as_str() != "-") {
+                entry.attributes.insert(key.to_string(), value.as_str().to_string());
+            }
+        }
+        Some(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn login_paths_match_whole_segments() {
+        let config = AccessLogConfig {
+            login_paths: vec!["/".into(), "".into(), " ".into(), "/auth/".into()],
+            ..AccessLogConfig::default()
+        };
+        assert!(config.is_login_path("/auth"));
+        assert!(config.is_login_path("/auth/token?next=/"));
+        assert!(!config.is_login_path("/authors"));
+        assert!(!config.is_login_path("/"));
+        assert!(!config.is_login_path("/index.html"));
+    }
+}
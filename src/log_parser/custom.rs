@@ -0,0 +1,156 @@
+use std::fs;
+use std::sync::Arc;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::{split_continuation, Framing, LogParser, MultilineConfig};
+use crate::log_analyzer::{Level, LogEntry, Outcome};
+use crate::timestamp::TimeResolver;
+
+pub const CUSTOM_FORMATS_FILE: &str = "log_hawk_formats.toml";
+
+/// A user defined format as written in the formats file:
+///
+/// ```toml
+/// [[format]]
+/// name = "billing"
+/// display_name = "Billing API"
+/// pattern = '^(?P<ts>\S+) (?P<user>\S+) (?P<status>OK|DENIED) (?P<ip>\S+) (?P<msg>.*)$'
+/// success = ["OK"]
+/// failure = ["DENIED"]
+/// multiline_start = '^\d{4}-'
+/// ```
+///
+/// `ts`, `user`, `status`, `ip`, `level`, `action` and `msg` fill the entry,
+/// any other named group becomes an attribute. `ts` is read with
+/// `timestamp_format` (chrono syntax) when given, otherwise guessed. Without
+/// a `status` group the outcome comes from `success_pattern` /
+/// `failure_pattern` matched against the line. `multiline_start` and
+/// `multiline_indented` group continuation lines into the record before
+/// them, as for `MultilineConfig`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomFormat {
+    pub name: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    pub pattern: String,
+    #[serde(default)]
+    pub timestamp_format: Option<String>,
+    #[serde(default)]
+    pub success: Vec<String>,
+    #[serde(default)]
+    pub failure: Vec<String>,
+    #[serde(default)]
+    pub success_pattern: Option<String>,
+    #[serde(default)]
+    pub failure_pattern: Option<String>,
+    #[serde(default)]
+    pub multiline_start: Option<String>,
+    #[serde(default)]
+    pub multiline_indented: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CustomFormatsFile {
+    #[serde(default, rename = "format")]
+    formats: Vec<CustomFormat>,
+}
+
+pub fn load_custom_formats(path: &str) -> Result<Vec<CustomRegexParser>, String> {
+    let contents = fs::read_to_string(path).map_err(|err| format!("Не удалось прочитать {}: {}", path, err))?;
+    let file: CustomFormatsFile = toml::from_str(&contents).map_err(|err| format!("Ошибка в {}: {}", path, err))?;
+    file.formats.into_iter().map(CustomRegexParser::new).collect()
+}
+
+pub struct CustomRegexParser {
+    format: CustomFormat,
+    re_line: Regex,
+    re_success: Option<Regex>,
+    re_failure: Option<Regex>,
+    framing: Framing,
+}
+
+impl CustomRegexParser {
+    pub fn new(format: CustomFormat) -> Result<Self, String> {
+        let compile = |pattern: &str| {
+            Regex::new(pattern).map_err(|err| format!("Формат «{}»: некорректное выражение: {}", format.name, err))
+        };
+        let re_line = compile(&format.pattern)?;
+        let re_success = format.success_pattern.as_deref().map(compile).transpose()?;
+        let re_failure = format.failure_pattern.as_deref().map(compile).transpose()?;
+        let multiline = MultilineConfig {
+            start_pattern: format.multiline_start.clone().unwrap_or_default(),
+            indented: format.multiline_indented,
+        };
+        let framing = multiline.framing().map_err(|err| format!("Формат «{}»: {}", format.name, err))?;
+        Ok(Self { format, re_line, re_success, re_failure, framing })
+    }
+
+    fn outcome(&self, status: Option<&str>, line: &str) -> Option<bool> {
+        if let Some(status) = status {
+            let matches = |values: &[String]| values.iter().any(|v| v.eq_ignore_ascii_case(status));
+            if matches(&self.format.success) {
+                return Some(true);
+            }
+            if matches(&self.format.failure) {
+                return Some(false);
+            }
+        }
+        if self.re_failure.as_ref().is_some_and(|re| re.is_match(line)) {
+            return Some(false);
+        }
+        if self.re_success.as_ref().is_some_and(|re| re.is_match(line)) {
+            return Some(true);
+        }
+        None
+    }
+}
+
+impl LogParser for CustomRegexParser {
+    fn name(&self) -> &str {
+        &self.format.name
+    }
+
+    fn display_name(&self) -> &str {
+        self.format.display_name.as_deref().unwrap_or(&self.format.name)
+    }
+
+    fn framing(&self) -> Framing {
+        self.framing.clone()
+    }
+
+    fn parse_line(&self, record: &str, time: &mut TimeResolver) -> Option<LogEntry> {
+        let (line, continuation) = split_continuation(record);
+        let caps = self.re_line.captures(line)?;
+        let group = |name: &str| caps.name(name).map(|m| m.as_str());
+
+        let status = group("status");
+        let mut entry = LogEntry {
+            timestamp: group("ts").and_then(|ts| match &self.format.timestamp_format {
+                Some(format) => time.with_format(ts, format),
+                None => time.any(ts),
+            }),
+            outcome: self.outcome(status, line).map_or(Outcome::Unknown, Outcome::from_success),
+            level: group("level").and_then(Level::parse),
+            action: group("action").filter(|action| !action.is_empty()).map(Arc::from),
+            message: group("msg").unwrap_or(line).to_string(),
+            ..Default::default()
+        };
+        entry.set_user(group("user").unwrap_or_default());
+        entry.set_client(group("ip").unwrap_or_default());
+        if let Some(status) = status {
+            entry.attributes.insert("status".to_string(), status.to_string());
+        }
+        for name in self.re_line.capture_names().flatten() {
+            if matches!(name, "ts" | "user" | "status" | "ip" | "level" | "action" | "msg") {
+                continue;
+            }
+            if let Some(value) = group(name) {
+                entry.attributes.insert(name.to_string(), value.to_string());
+            }
+        }
+        entry.append_continuation(continuation);
+        Some(entry)
+    }
+}
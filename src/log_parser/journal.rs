@@ -0,0 +1,249 @@
+use std::borrow::Cow;
+use std::io::{self, BufRead, Read};
+
+use chrono::DateTime;
+use serde_json::{Map, Value};
+
+use super::syslog::{FACILITIES, SEVERITIES};
+use super::{Framing, LogParser, RecordReader, SshdParser};
+use crate::log_analyzer::{Level, LogEntry};
+use crate::timestamp::TimeResolver;
+
+/// Binary fields longer than this are taken as a corrupt stream.
+const MAX_FIELD_LEN: u64 = 64 << 20;
+
+/// `journalctl -o json`: one JSON object per entry.
+pub struct JournalParser {
+    sshd: SshdParser,
+}
+
+impl Default for JournalParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JournalParser {
+    pub fn new() -> Self {
+        Self { sshd: SshdParser::new() }
+    }
+}
+
+impl LogParser for JournalParser {
+    fn name(&self) -> &str {
+        "journal"
+    }
+
+    fn display_name(&self) -> &str {
+        "systemd journal (JSON)"
+    }
+
+    fn parse_line(&self, line: &str, _time: &mut TimeResolver) -> Option<LogEntry> {
+        let line = line.trim();
+        if !line.starts_with('{') {
+            return None;
+        }
+        let object = match serde_json::from_str::<Value>(line).ok()? {
+            Value::Object(object) => object,
+            _ => return None,
+        };
+        // Required, which also keeps other JSON logs out.
+        let micros: i64 = field(&object, "__REALTIME_TIMESTAMP")?.trim().parse().ok()?;
+
+        let mut entry = LogEntry {
+            timestamp: DateTime::from_timestamp_micros(micros),
+            ..Default::default()
+        };
+        if let Some(severity) = field(&object, "PRIORITY").and_then(|p| p.trim().parse::<usize>().ok()).and_then(|p| SEVERITIES.get(p)) {
+            entry.attributes.insert("severity", *severity);
+            entry.level = Level::parse(severity);
+        }
+        if let Some(facility) = field(&object, "SYSLOG_FACILITY").and_then(|f| f.trim().parse::<usize>().ok()).and_then(|f| FACILITIES.get(f)) {
+            entry.attributes.insert("facility", *facility);
+        }
+        let app = field(&object, "SYSLOG_IDENTIFIER").or_else(|| field(&object, "_COMM"));
+        for (key, name) in [("_HOSTNAME", "host"), ("_PID", "pid"), ("_SYSTEMD_UNIT", "unit")] {
+            if let Some(value) = field(&object, key) {
+                entry.attributes.insert(name, value);
+            }
+        }
+        if let Some(app) = &app {
+            entry.attributes.insert("app", app.as_ref());
+        }
+
+        let message = field(&object, "MESSAGE").unwrap_or_default();
+        match app.as_deref() {
+            Some(app) if SshdParser::is_sshd(app) => self.sshd.parse_message(&message, &mut entry),
+            Some(app) => entry.message = format!("{}: {}", app, message),
+            None => entry.message = message.into_owned(),
+        }
+        Some(entry)
+    }
+}
+
+/// `journalctl -o export`: `FIELD=value` lines, length-prefixed binary
+/// fields, and an empty line after each entry. The framing turns every
+/// entry into the object `-o json` would print, so both share one parser.
+#[derive(Default)]
+pub struct JournalExportParser {
+    json: JournalParser,
+}
+
+impl LogParser for JournalExportParser {
+    fn name(&self) -> &str {
+        "journal-export"
+    }
+
+    fn display_name(&self) -> &str {
+        "systemd journal (export)"
+    }
+
+    fn framing(&self) -> Framing {
+        Framing::JournalExport
+    }
+
+    fn parse_line(&self, line: &str, time: &mut TimeResolver) -> Option<LogEntry> {
+        self.json.parse_line(line, time)
+    }
+}
+
+/// The text of a field as journalctl prints it: a string, an array of bytes
+/// for binary data, or an array of either when the field repeats.
+fn field<'a>(object: &'a Map<String, Value>, key: &str) -> Option<Cow<'a, str>> {
+    match object.get(key)? {
+        Value::String(value) => Some(Cow::Borrowed(value)),
+        Value::Array(items) if items.iter().all(Value::is_u64) => {
+            let bytes: Vec<u8> = items.iter().filter_map(Value::as_u64).map(|b| b as u8).collect();
+            Some(Cow::Owned(String::from_utf8_lossy(&bytes).into_owned()))
+        }
+        Value::Array(items) => items.iter().find_map(|item| match item {
+            Value::String(value) => Some(Cow::Borrowed(value.as_str())),
+            _ => None,
+        }),
+        Value::Number(number) => Some(Cow::Owned(number.to_string())),
+        _ => None,
+    }
+}
+
+impl<R: BufRead> RecordReader<R> {
+    pub(super) fn next_journal_entry(&mut self, record: &mut Vec<u8>) -> io::Result<Option<usize>> {
+        let mut fields = Map::new();
+        let mut start = None;
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if self.reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            self.line_number += 1;
+            if line == b"\n" {
+                if start.is_some() {
+                    break;
+                }
+                continue;
+            }
+            start.get_or_insert(self.line_number);
+            if line.last() == Some(&b'\n') {
+                line.pop();
+            }
+
+            let (name, value) = match line.iter().position(|&b| b == b'=') {
+                Some(equals) => (&line[..equals], Value::String(String::from_utf8_lossy(&line[equals + 1..]).into_owned())),
+                None => {
+                    let mut prefix = [0; 8];
+                    self.reader.read_exact(&mut prefix)?;
+                    let length = u64::from_le_bytes(prefix);
+                    if length > MAX_FIELD_LEN {
+                        return Err(io::Error::new(io::ErrorKind::InvalidData, "слишком длинное поле журнала"));
+                    }
+                    let mut data = Vec::with_capacity(length as usize);
+                    (&mut self.reader).take(length).read_to_end(&mut data)?;
+                    if data.len() as u64 != length {
+                        return Err(io::ErrorKind::UnexpectedEof.into());
+                    }
+                    let mut newline = [0; 1];
+                    self.reader.read_exact(&mut newline)?;
+                    self.line_number += super::count_lines(&prefix) + super::count_lines(&data) + 1;
+                    let value = match String::from_utf8(data) {
+                        Ok(text) => Value::String(text),
+                        Err(err) => Value::Array(err.into_bytes().into_iter().map(Value::from).collect()),
+                    };
+                    (&line[..], value)
+                }
+            };
+            let name = String::from_utf8_lossy(name).into_owned();
+            fields.entry(name).or_insert(value);
+        }
+
+        let Some(start) = start else {
+            return Ok(None);
+        };
+        serde_json::to_writer(&mut *record, &Value::Object(fields)).map_err(io::Error::other)?;
+        Ok(Some(start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timestamp::Zone;
+
+    fn binary_field(name: &str, data: &[u8]) -> Vec<u8> {
+        let mut field = format!("{}\n", name).into_bytes();
+        field.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        field.extend_from_slice(data);
+        field.push(b'\n');
+        field
+    }
+
+    #[test]
+    fn export_reads_binary_fields_across_lines() {
+        let mut export = b"__REALTIME_TIMESTAMP=1700000000000000\n".to_vec();
+        export.extend(binary_field("MESSAGE", b"line one\nline two"));
+        export.extend_from_slice(b"_HOSTNAME=web1\n\n__REALTIME_TIMESTAMP=1700000001000000\nMESSAGE=second\n\n");
+
+        let mut reader = RecordReader::new(&export[..], Framing::JournalExport);
+        let mut record = Vec::new();
+        assert_eq!(reader.next_record(&mut record).unwrap(), Some(1));
+        let first = String::from_utf8(record.clone()).unwrap();
+        // `MESSAGE` plus the two lines of its value, then `_HOSTNAME` and the separator.
+        assert_eq!(reader.next_record(&mut record).unwrap(), Some(7));
+        let second = String::from_utf8(record.clone()).unwrap();
+        assert_eq!(reader.next_record(&mut record).unwrap(), None);
+        assert_eq!(reader.line_number(), 9);
+
+        let parser = JournalExportParser::default();
+        let mut time = TimeResolver::new(Zone::Utc);
+        let entry = parser.parse_line(&first, &mut time).unwrap();
+        assert_eq!(entry.message, "line one\nline two");
+        assert_eq!(entry.attributes.get("host"), Some("web1"));
+        assert_eq!(entry.timestamp, DateTime::from_timestamp(1_700_000_000, 0));
+        assert_eq!(parser.parse_line(&second, &mut time).unwrap().message, "second");
+    }
+
+    #[test]
+    fn export_keeps_invalid_utf8_as_bytes() {
+        let mut export = b"__REALTIME_TIMESTAMP=1\n".to_vec();
+        export.extend(binary_field("MESSAGE", b"caf\xe9"));
+        let mut record = Vec::new();
+        RecordReader::new(&export[..], Framing::JournalExport).next_record(&mut record).unwrap();
+        let object: Value = serde_json::from_slice(&record).unwrap();
+        assert_eq!(object["MESSAGE"], serde_json::json!([99, 97, 102, 233]));
+        let entry = JournalParser::new().parse_line(std::str::from_utf8(&record).unwrap(), &mut TimeResolver::new(Zone::Utc)).unwrap();
+        assert_eq!(entry.message, "caf\u{fffd}");
+    }
+
+    #[test]
+    fn export_rejects_truncated_and_oversized_fields() {
+        let mut truncated = b"__REALTIME_TIMESTAMP=1\nMESSAGE\n".to_vec();
+        truncated.extend_from_slice(&100u64.to_le_bytes());
+        truncated.extend_from_slice(b"short");
+        let error = RecordReader::new(&truncated[..], Framing::JournalExport).next_record(&mut Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+
+        let mut oversized = b"MESSAGE\n".to_vec();
+        oversized.extend_from_slice(&(MAX_FIELD_LEN + 1).to_le_bytes());
+        let error = RecordReader::new(&oversized[..], Framing::JournalExport).next_record(&mut Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+use super::LogParser;
+use crate::log_analyzer::{Level, LogEntry, Outcome};
+use crate::timestamp::TimeResolver;
+
+/// Which JSON keys feed which `LogEntry` field. Every field lists candidate
+/// keys tried in order; dotted keys (`http.client_ip`) descend into objects.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JsonFieldMapping {
+    pub timestamp: Vec<String>,
+    pub ip: Vec<String>,
+    pub user: Vec<String>,
+    pub status: Vec<String>,
+    pub message: Vec<String>,
+    pub level: Vec<String>,
+    pub action: Vec<String>,
+    pub success_values: Vec<String>,
+    pub failure_values: Vec<String>,
+}
+
+impl JsonFieldMapping {
+    pub fn fields_mut(&mut self) -> [(&'static str, &mut Vec<String>); 9] {
+        [
+            ("Время", &mut self.timestamp),
+            ("IP", &mut self.ip),
+            ("Пользователь", &mut self.user),
+            ("Статус", &mut self.status),
+            ("Сообщение", &mut self.message),
+            ("Уровень", &mut self.level),
+            ("Действие", &mut self.action),
+            ("Успех =", &mut self.success_values),
+            ("Неудача =", &mut self.failure_values),
+        ]
+    }
+}
+
+impl Default for JsonFieldMapping {
+    fn default() -> Self {
+        let keys = |keys: &[&str]| keys.iter().map(|k| k.to_string()).collect();
+        Self {
+            timestamp: keys(&["@t", "timestamp", "@timestamp", "time", "ts"]),
+            ip: keys(&["ClientIp", "client_ip", "clientIp", "ip", "remote_addr", "src_ip"]),
+            user: keys(&["User", "user", "UserName", "username"]),
+            status: keys(&["Status", "status", "success", "outcome"]),
+            message: keys(&["@m", "Messages", "message", "msg"]),
+            level: keys(&["@l", "level", "severity"]),
+            action: keys(&["ActionName", "action", "event"]),
+            success_values: keys(&["true", "success", "succeeded", "ok"]),
+            failure_values: keys(&["false", "failure", "failed", "fail", "denied"]),
+        }
+    }
+}
+
+pub struct JsonParser {
+    mapping: JsonFieldMapping,
+}
+
+impl Default for JsonParser {
+    fn default() -> Self {
+        Self::new(JsonFieldMapping::default())
+    }
+}
+
+impl JsonParser {
+    pub fn new(mapping: JsonFieldMapping) -> Self {
+        Self { mapping }
+    }
+
+    fn outcome(&self, value: Option<&Value>) -> Outcome {
+        let raw = match value {
+            None | Some(Value::Null) => return Outcome::Unknown,
+            Some(value) => scalar_to_string(value),
+        };
+        let matches = |values: &[String]| values.iter().any(|v| v.eq_ignore_ascii_case(&raw));
+        if matches(&self.mapping.success_values) {
+            Outcome::Success
+        } else if matches(&self.mapping.failure_values) {
+            Outcome::Failure
+        } else {
+            Outcome::Unknown
+        }
+    }
+}
+
+impl LogParser for JsonParser {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn display_name(&self) -> &str {
+        "JSON Lines / Serilog CLEF"
+    }
+
+    fn parse_line(&self, line: &str, time: &mut TimeResolver) -> Option<LogEntry> {
+        let line = line.trim();
+        if !line.starts_with('{') {
+            return None;
+        }
+        let object = match serde_json::from_str::<Value>(line).ok()? {
+            Value::Object(object) => object,
+            _ => return None,
+        };
+
+        let field = |keys: &[String]| keys.iter().find_map(|key| lookup(&object, key));
+        let text = |keys: &[String]| field(keys).map(scalar_to_string);
+
+        let message = text(&self.mapping.message)
+            .or_else(|| object.get("@mt").and_then(Value::as_str).map(|template| render_template(template, &object)))
+            .unwrap_or_default();
+
+        let mut entry = LogEntry {
+            timestamp: text(&self.mapping.timestamp).and_then(|ts| time.any(&ts)),
+            outcome: self.outcome(field(&self.mapping.status)),
+            action: text(&self.mapping.action).filter(|action| !action.is_empty()).map(Arc::from),
+            message,
+            ..Default::default()
+        };
+        if let Some(user) = text(&self.mapping.user) {
+            entry.set_user(&user);
+        }
+        if let Some(ip) = text(&self.mapping.ip) {
+            entry.set_client(&ip);
+        }
+
+        // CLEF omits @l for the Information level.
+        entry.level = match text(&self.mapping.level) {
+            Some(level) => Level::parse(&level),
+            None if object.contains_key("@t") => Some(Level::Info),
+            None => None,
+        };
+        if let Some(exception) = object.get("@x").and_then(Value::as_str) {
+            entry.attributes.insert("exception".to_string(), exception.to_string());
+        }
+        for (key, value) in &object {
+            if !key.starts_with('@') && !value.is_object() && !value.is_array() {
+                entry.attributes.insert(key.clone(), scalar_to_string(value));
+            }
+        }
+        Some(entry)
+    }
+}
+
+fn lookup<'a>(object: &'a Map<String, Value>, key: &str) -> Option<&'a Value> {
+    if let Some(value) = object.get(key) {
+        return Some(value);
+    }
+    let (head, tail) = key.split_once('.')?;
+    match object.get(head)? {
+        Value::Object(inner) => lookup(inner, tail),
+        _ => None,
+    }
+}
+
+fn scalar_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Renders a Serilog message template (`{User}`, `{@Payload}`, `{Ip:l}`)
+/// from the event properties, keeping unknown holes verbatim.
+fn render_template(template: &str, properties: &Map<String, Value>) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        rendered.push_str(&rest[..start]);
+        let tail = &rest[start..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            rendered.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+        if let Some(after) = tail.strip_prefix('}') {
+            rendered.push('}');
+            rest = after;
+            continue;
+        }
+        let Some(end) = tail.find('}') else {
+            rendered.push_str(tail);
+            return rendered;
+        };
+        let hole = &tail[1..end];
+        let name = hole
+            .trim_start_matches(['@', '$'])
+            .split([',', ':'])
+            .next()
+            .unwrap_or_default();
+        match properties.get(name) {
+            Some(value) => rendered.push_str(&scalar_to_string(value)),
+            None => rendered.push_str(&tail[..=end]),
+        }
+        rest = &tail[end + 1..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
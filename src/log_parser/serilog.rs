@@ -0,0 +1,100 @@
+use regex::Regex;
+
+use super::{split_continuation, Framing, LogParser, MultilineConfig};
+use crate::log_analyzer::{Level, LogEntry, Outcome};
+use crate::timestamp::TimeResolver;
+
+/// With grouping turned on, a record starts with its `[HH:MM:SS LVL]`
+/// header and the lines up to the next header are the exception logged with it.
+const RECORD_START: &str = r"^\[\d{2}:\d{2}:\d{2}";
+
+pub struct SerilogParser {
+    re_auth: Regex,
+    re_event: Regex,
+    framing: Framing,
+}
+
+impl Default for SerilogParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SerilogParser {
+    /// Every line is a record, so stray lines are reported as rejected
+    /// rather than folded into the record above them.
+    pub fn new() -> Self {
+        Self::with_framing(Framing::Line)
+    }
+
+    /// Groups lines as `multiline` says instead of by the record header.
+    pub fn with_multiline(multiline: &MultilineConfig) -> Result<Self, String> {
+        Ok(Self::with_framing(multiline.framing()?))
+    }
+
+    /// Grouping by the record header, for logs with stack traces.
+    pub fn record_start_multiline() -> MultilineConfig {
+        MultilineConfig { start_pattern: RECORD_START.to_string(), indented: false }
+    }
+
+    fn with_framing(framing: Framing) -> Self {
+        Self {
+            framing,
+            re_auth: Regex::new(r"\[(\d{2}:\d{2}:\d{2}(?:\.\d+)?) (\w{3})\] User:(\w+) Status:(\w+) Messages:(.*?) ActionName:(\w+) ClientIp:(\[[0-9A-Fa-f:.]+(?:%[\w.-]+)?\](?::\d+)?|[0-9A-Fa-f:.]+(?:%[\w.-]+)?)").unwrap(),
+            re_event: Regex::new(r"^\[(\d{2}:\d{2}:\d{2}(?:\.\d+)?) (\w{3})\] (.*)$").unwrap(),
+        }
+    }
+}
+
+impl LogParser for SerilogParser {
+    fn name(&self) -> &str {
+        "serilog"
+    }
+
+    fn display_name(&self) -> &str {
+        "Serilog (текст)"
+    }
+
+    fn framing(&self) -> Framing {
+        self.framing.clone()
+    }
+
+    fn parse_line(&self, record: &str, time: &mut TimeResolver) -> Option<LogEntry> {
+        let (line, continuation) = split_continuation(record);
+        let mut entry = self.parse_header(line, time)?;
+        entry.append_continuation(continuation);
+        Some(entry)
+    }
+}
+
+impl SerilogParser {
+    fn parse_header(&self, line: &str, time: &mut TimeResolver) -> Option<LogEntry> {
+        let Some(caps) = self.re_auth.captures(line) else {
+            // Events without the auth fields (warnings, errors) still carry a level.
+            let caps = self.re_event.captures(line)?;
+            return Some(LogEntry {
+                timestamp: time.clock(&caps[1]),
+                level: Some(Level::parse(&caps[2])?),
+                message: caps[3].to_string(),
+                ..Default::default()
+            });
+        };
+        let mut entry = LogEntry {
+            timestamp: time.clock(&caps[1]),
+            level: Some(Level::parse(&caps[2])?),
+            action: Some(caps[6].into()),
+            message: caps[5].to_string(),
+            ..Default::default()
+        };
+        entry.set_user(&caps[3]);
+        entry.set_client(&caps[7]);
+        match caps[4].to_ascii_lowercase().as_str() {
+            "true" => entry.outcome = Outcome::Success,
+            "false" => entry.outcome = Outcome::Failure,
+            status => {
+                entry.attributes.insert("status".to_string(), status.to_string());
+            }
+        }
+        Some(entry)
+    }
+}
@@ -0,0 +1,105 @@
+use regex::Regex;
+
+use super::LogParser;
+use crate::log_analyzer::{LogEntry, Outcome};
+use crate::timestamp::TimeResolver;
+
+pub struct SshdParser {
+    re_header: Regex,
+    re_repeated: Regex,
+    re_auth: Regex,
+    re_invalid_user: Regex,
+    re_pam_failure: Regex,
+    re_peer: Regex,
+}
+
+impl Default for SshdParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SshdParser {
+    pub fn new() -> Self {
+        Self {
+            re_header: Regex::new(r"^(?P<ts>[A-Z][a-z]{2}\s+\d{1,2}\s+\d{2}:\d{2}:\d{2}|\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}(?:\.\d+)?(?:Z|[+-]\d{2}:?\d{2})?)\s+(?P<host>\S+)\s+(?P<prog>sshd(?:-session)?)(?:\[(?P<pid>\d+)\])?:\s+(?P<msg>.*)$").unwrap(),
+            re_repeated: Regex::new(r"^message repeated (?P<count>\d+) times: \[\s*(?P<msg>.*?)\s*\]$").unwrap(),
+            re_auth: Regex::new(r"^(?P<result>Accepted|Failed) (?P<method>\S+) for (?P<invalid>invalid user )?(?P<user>\S*) from (?P<ip>\S+) port (?P<port>\d+)").unwrap(),
+            re_invalid_user: Regex::new(r"^Invalid user (?P<user>\S*) from (?P<ip>\S+)(?: port (?P<port>\d+))?").unwrap(),
+            re_pam_failure: Regex::new(r"^pam_unix\(sshd:auth\): authentication failure;.*\brhost=(?P<ip>\S*)(?:\s+user=(?P<user>\S+))?").unwrap(),
+            re_peer: Regex::new(r"\b(?:from|by)(?: authenticating user| invalid user| user)? (?:(?P<user>\S+) )?(?P<ip>\S+) port (?P<port>\d+)").unwrap(),
+        }
+    }
+}
+
+impl LogParser for SshdParser {
+    fn name(&self) -> &str {
+        "sshd"
+    }
+
+    fn display_name(&self) -> &str {
+        "OpenSSH (auth.log / secure)"
+    }
+
+    fn parse_line(&self, line: &str, time: &mut TimeResolver) -> Option<LogEntry> {
+        let header = self.re_header.captures(line)?;
+
+        let mut entry = LogEntry {
+            timestamp: time.any(&header["ts"]),
+            ..Default::default()
+        };
+        entry.attributes.insert("host".to_string(), header["host"].to_string());
+        if let Some(pid) = header.name("pid") {
+            entry.attributes.insert("pid".to_string(), pid.as_str().to_string());
+        }
+        self.parse_message(header.name("msg").map_or("", |m| m.as_str()), &mut entry);
+        Some(entry)
+    }
+}
+
+impl SshdParser {
+    /// Fills outcome, user, IP and sshd specific attributes from the message
+    /// part of an sshd line, so other transports (syslog, journal) can reuse it.
+    pub fn parse_message(&self, mut message: &str, entry: &mut LogEntry) {
+        if let Some(repeated) = self.re_repeated.captures(message) {
+            entry.repeated = repeated["count"].parse().ok().filter(|&count| count > 0);
+            message = repeated.name("msg").map_or("", |m| m.as_str());
+        }
+
+        if let Some(caps) = self.re_auth.captures(message) {
+            entry.outcome = Outcome::from_success(&caps["result"] == "Accepted");
+            entry.set_user(&caps["user"]);
+            entry.set_client(&caps["ip"]);
+            entry.attributes.insert("port".to_string(), caps["port"].to_string());
+            entry.attributes.insert("auth_method".to_string(), caps["method"].to_string());
+            if caps.name("invalid").is_some() {
+                entry.attributes.insert("invalid_user".to_string(), "true".to_string());
+            }
+        } else if let Some(caps) = self.re_invalid_user.captures(message) {
+            entry.set_user(&caps["user"]);
+            entry.set_client(&caps["ip"]);
+            if let Some(port) = caps.name("port") {
+                entry.attributes.insert("port".to_string(), port.as_str().to_string());
+            }
+            entry.attributes.insert("invalid_user".to_string(), "true".to_string());
+        } else if let Some(caps) = self.re_pam_failure.captures(message) {
+            if let Some(user) = caps.name("user") {
+                entry.set_user(user.as_str());
+            }
+            entry.set_client(&caps["ip"]);
+            entry.attributes.insert("auth_method".to_string(), "pam".to_string());
+        } else if let Some(caps) = self.re_peer.captures(message) {
+            if let Some(user) = caps.name("user") {
+                entry.set_user(user.as_str());
+            }
+            entry.set_client(&caps["ip"]);
+            entry.attributes.insert("port".to_string(), caps["port"].to_string());
+        }
+
+        entry.message = message.to_string();
+    }
+
+    pub fn is_sshd(app_name: &str) -> bool {
+        matches!(app_name, "sshd" | "sshd-session")
+    }
+}
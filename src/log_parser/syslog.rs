@@ -0,0 +1,216 @@
+use regex::Regex;
+
+use super::{LogParser, SshdParser};
+use crate::log_analyzer::{Level, LogEntry};
+use crate::timestamp::TimeResolver;
+
+pub(super) const FACILITIES: [&str; 24] = [
+    "kern", "user", "mail", "daemon", "auth", "syslog", "lpr", "news",
+    "uucp", "cron", "authpriv", "ftp", "ntp", "security", "console", "solaris-cron",
+    "local0", "local1", "local2", "local3", "local4", "local5", "local6", "local7",
+];
+
+pub(super) const SEVERITIES: [&str; 8] = [
+    "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+pub struct SyslogParser {
+    re_rfc5424: Regex,
+    re_rfc3164: Regex,
+    sshd: SshdParser,
+}
+
+impl Default for SyslogParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SyslogParser {
+    pub fn new() -> Self {
+        Self {
+            re_rfc5424: Regex::new(r"^<(?P<pri>\d{1,3})>(?P<version>\d{1,2}) (?P<ts>\S+) (?P<host>\S+) (?P<app>\S+) (?P<procid>\S+) (?P<msgid>\S+) (?P<rest>.*)$").unwrap(),
+            re_rfc3164: Regex::new(r"^(?:<(?P<pri>\d{1,3})>)?(?P<ts>[A-Z][a-z]{2}\s+\d{1,2}\s+\d{2}:\d{2}:\d{2}|\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}(?:\.\d+)?(?:Z|[+-]\d{2}:?\d{2})?)\s+(?P<host>\S+)\s+(?P<tag>[^\s:\[]+)(?:\[(?P<pid>[^\]]*)\])?:\s?(?P<msg>.*)$").unwrap(),
+            sshd: SshdParser::new(),
+        }
+    }
+
+    fn parse_rfc5424(&self, line: &str, time: &mut TimeResolver) -> Option<LogEntry> {
+        let caps = self.re_rfc5424.captures(line)?;
+        let (structured_data, message) = split_structured_data(&caps["rest"])?;
+
+        let mut entry = LogEntry {
+            timestamp: time.any(nil_to_empty(&caps["ts"])),
+            ..Default::default()
+        };
+        insert_priority(&mut entry, &caps["pri"])?;
+        entry.attributes.insert("version".to_string(), caps["version"].to_string());
+        for (key, name) in [("host", "host"), ("app", "app"), ("procid", "pid"), ("msgid", "msgid")] {
+            let value = nil_to_empty(&caps[key]);
+            if !value.is_empty() {
+                entry.attributes.insert(name.to_string(), value.to_string());
+            }
+        }
+        for (id, params) in structured_data {
+            for (name, value) in params {
+                entry.attributes.insert(format!("sd.{}.{}", id, name), value);
+            }
+        }
+
+        let message = message.trim_start_matches('\u{feff}');
+        self.fill_message(&mut entry, nil_to_empty(&caps["app"]), message);
+        Some(entry)
+    }
+
+    fn parse_rfc3164(&self, line: &str, time: &mut TimeResolver) -> Option<LogEntry> {
+        let caps = self.re_rfc3164.captures(line)?;
+
+        let mut entry = LogEntry {
+            timestamp: time.any(&caps["ts"]),
+            ..Default::default()
+        };
+        if let Some(pri) = caps.name("pri") {
+            insert_priority(&mut entry, pri.as_str())?;
+        }
+        entry.attributes.insert("host".to_string(), caps["host"].to_string());
+        entry.attributes.insert("app".to_string(), caps["tag"].to_string());
+        if let Some(pid) = caps.name("pid") {
+            entry.attributes.insert("pid".to_string(), pid.as_str().to_string());
+        }
+
+        self.fill_message(&mut entry, &caps["tag"], &caps["msg"]);
+        Some(entry)
+    }
+
+    fn fill_message(&self, entry: &mut LogEntry, app: &str, message: &str) {
+        if SshdParser::is_sshd(app) {
+            self.sshd.parse_message(message, entry);
+        } else {
+            entry.message = if app.is_empty() {
+                message.to_string()
+            } else {
+                format!("{}: {}", app, message)
+            };
+        }
+    }
+}
+
+impl LogParser for SyslogParser {
+    fn name(&self) -> &str {
+        "syslog"
+    }
+
+    fn display_name(&self) -> &str {
+        "Syslog (RFC 3164 / RFC 5424)"
+    }
+
+    fn parse_line(&self, line: &str, time: &mut TimeResolver) -> Option<LogEntry> {
+        self.parse_rfc5424(line, time).or_else(|| self.parse_rfc3164(line, time))
+    }
+}
+
+fn nil_to_empty(value: &str) -> &str {
+    if value == "-" { "" } else { value }
+}
+
+fn insert_priority(entry: &mut LogEntry, pri: &str) -> Option<()> {
+    let pri: usize = pri.parse().ok()?;
+    let facility = FACILITIES.get(pri / 8)?;
+    entry.attributes.insert("facility".to_string(), facility.to_string());
+    entry.attributes.insert("severity".to_string(), SEVERITIES[pri % 8].to_string());
+    entry.level = Level::parse(SEVERITIES[pri % 8]);
+    Some(())
+}
+
+type StructuredData = Vec<(String, Vec<(String, String)>)>;
+
+/// Splits the RFC 5424 STRUCTURED-DATA part from the message, unescaping
+/// `\"`, `\\` and `\]` inside parameter values.
+fn split_structured_data(rest: &str) -> Option<(StructuredData, &str)> {
+    if let Some(message) = rest.strip_prefix('-') {
+        return Some((Vec::new(), message.strip_prefix(' ').unwrap_or(message)));
+    }
+
+    let mut elements = Vec::new();
+    let mut chars = rest.char_indices().peekable();
+    while let Some(&(_, '[')) = chars.peek() {
+        chars.next();
+        let mut id = String::new();
+        while let Some(&(_, c)) = chars.peek() {
+            if c == ' ' || c == ']' {
+                break;
+            }
+            id.push(c);
+            chars.next();
+        }
+
+        let mut params = Vec::new();
+        loop {
+            match chars.next()? {
+                (_, ']') => break,
+                (_, ' ') => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next()? {
+                            (_, '=') => break,
+                            (_, c) => name.push(c),
+                        }
+                    }
+                    if chars.next()?.1 != '"' {
+                        return None;
+                    }
+                    let mut value = String::new();
+                    loop {
+                        match chars.next()? {
+                            (_, '\\') => {
+                                let (_, escaped) = chars.next()?;
+                                if !matches!(escaped, '"' | '\\' | ']') {
+                                    value.push('\\');
+                                }
+                                value.push(escaped);
+                            }
+                            (_, '"') => break,
+                            (_, c) => value.push(c),
+                        }
+                    }
+                    params.push((name, value));
+                }
+                _ => return None,
+            }
+        }
+        elements.push((id, params));
+    }
+
+    let message = match chars.next() {
+        None => "",
+        Some((i, ' ')) => &rest[i + 1..],
+        Some(_) => return None,
+    };
+    Some((elements, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn structured_data_unescapes_values() {
+        let rest = r#"[origin@1 note="say \"hi\"" path="a\]b" dir="C:\temp" slash="\\"][empty@2] Message text"#;
+        let (data, message) = split_structured_data(rest).unwrap();
+        let param = |name: &str, value: &str| (name.to_string(), value.to_string());
+        assert_eq!(data, vec![
+            ("origin@1".to_string(), vec![param("note", r#"say "hi""#), param("path", "a]b"), param("dir", r"C:\temp"), param("slash", r"\")]),
+            ("empty@2".to_string(), Vec::new()),
+        ]);
+        assert_eq!(message, "Message text");
+    }
+
+    #[test]
+    fn structured_data_nil_and_malformed() {
+        assert_eq!(split_structured_data("- Message"), Some((Vec::new(), "Message")));
+        assert_eq!(split_structured_data("[id@1]"), Some((vec![("id@1".to_string(), Vec::new())], "")));
+        assert_eq!(split_structured_data(r#"[id@1 a="unterminated] msg"#), None);
+        assert_eq!(split_structured_data("[id@1 a=b] msg"), None);
+        assert_eq!(split_structured_data("[id@1]msg"), None);
+    }
+}
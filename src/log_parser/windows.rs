@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::io::{self, Read, Seek};
+use std::sync::Arc;
+
+use evtx::{EvtxParser, IntoIterChunks, ParserSettings};
+
+use super::{Framing, LogParser};
+use crate::log_analyzer::{Level, LogEntry, Outcome};
+use crate::timestamp::TimeResolver;
+
+pub const EVTX_MAGIC: &[u8] = b"ElfFile\0";
+
+/// Renders the records of an exported `.evtx` file as a stream of `<Event>`
+/// XML documents, the same shape `wevtutil qe /f:xml` produces. Records are
+/// decoded one 64 KiB chunk at a time, so memory does not grow with the file.
+pub struct EvtxReader<R: Read + Seek> {
+    chunks: IntoIterChunks<R>,
+    settings: Arc<ParserSettings>,
+    buffer: io::Cursor<Vec<u8>>,
+    remaining: usize,
+}
+
+impl<R: Read + Seek> EvtxReader<R> {
+    pub fn new(reader: R, max_records: Option<usize>) -> io::Result<Self> {
+        let parser = EvtxParser::from_read_seek(reader).map_err(io::Error::other)?;
+        Ok(Self {
+            chunks: parser.into_chunks(),
+            settings: Arc::new(ParserSettings::default()),
+            buffer: io::Cursor::new(Vec::new()),
+            remaining: max_records.unwrap_or(usize::MAX),
+        })
+    }
+
+    fn next_chunk(&mut self) -> io::Result<bool> {
+        if self.remaining == 0 {
+            return Ok(false);
+        }
+        let Some(chunk) = self.chunks.next() else {
+            return Ok(false);
+        };
+        let mut chunk = chunk.map_err(io::Error::other)?;
+        let mut chunk = chunk.parse(self.settings.clone()).map_err(io::Error::other)?;
+        let mut xml = std::mem::take(self.buffer.get_mut());
+        xml.clear();
+        for record in chunk.iter().take(self.remaining) {
+            let record = record.map_err(io::Error::other)?.into_xml().map_err(io::Error::other)?;
+            xml.extend_from_slice(record.data.as_bytes());
+            xml.push(b'\n');
+            self.remaining -= 1;
+        }
+        self.buffer = io::Cursor::new(xml);
+        Ok(true)
+    }
+}
+
+impl<R: Read + Seek> Read for EvtxReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let read = self.buffer.read(out)?;
+            if read > 0 || out.is_empty() || !self.next_chunk()? {
+                return Ok(read);
+            }
+        }
+    }
+}
+
+pub struct WindowsEventParser;
+
+impl Default for WindowsEventParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WindowsEventParser {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl LogParser for WindowsEventParser {
+    fn name(&self) -> &str {
+        "windows"
+    }
+
+    fn display_name(&self) -> &str {
+        "Windows Security (EVTX / XML)"
+    }
+
+    fn framing(&self) -> Framing {
+        Framing::XmlElement("Event")
+    }
+
+    fn parse_line(&self, line: &str, time: &mut TimeResolver) -> Option<LogEntry> {
+        let document = roxmltree::Document::parse(line.trim()).ok()?;
+        let event = document.root_element();
+        if event.tag_name().name() != "Event" {
+            return None;
+        }
+        let system = child(event, "System")?;
+        let event_id: u32 = child(system, "EventID")?.text()?.trim().parse().ok()?;
+        let mut entry = LogEntry {
+            timestamp: child(system, "TimeCreated")
+                .and_then(|n| n.attribute("SystemTime"))
+                .and_then(|ts| time.any(ts)),
+            level: child(system, "Level").and_then(|n| n.text()).and_then(|level| match level.trim() {
+                "1" => Some(Level::Fatal),
+                "2" => Some(Level::Error),
+                "3" => Some(Level::Warning),
+                "0" | "4" => Some(Level::Info),
+                "5" => Some(Level::Trace),
+                _ => None,
+            }),
+            ..Default::default()
+        };
+        entry.attributes.insert("event_id".to_string(), event_id.to_string());
+        for (name, key) in [("Computer", "computer"), ("Channel", "channel"), ("EventRecordID", "record_id")] {
+            if let Some(text) = child(system, name).and_then(|n| n.text()) {
+                entry.attributes.insert(key.to_string(), text.trim().to_string());
+            }
+        }
+
+        let data: HashMap<&str, &str> = child(event, "EventData")
+            .map(|event_data| {
+                event_data
+                    .children()
+                    .filter(|n| n.is_element() && n.tag_name().name() == "Data")
+                    .filter_map(|n| Some((n.attribute("Name")?, n.text().unwrap_or_default().trim())))
+                    .filter(|(_, value)| !value.is_empty() && *value != "-")
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let failed = data.get("Status").is_some_and(|status| !is_success_status(status));
+        let (outcome, description) = match event_id {
+            4624 => (Some(true), "Успешный вход в систему"),
+            4625 => (Some(false), "Неудачный вход в систему"),
+            4771 => (Some(false), "Ошибка предварительной проверки Kerberos"),
+            4776 if failed => (Some(false), "Неудачная проверка учётных данных NTLM"),
+            4776 => (Some(true), "Проверка учётных данных NTLM"),
+            _ => (None, "Событие безопасности"),
+        };
+        if let Some(success) = outcome {
+            entry.outcome = Outcome::from_success(success);
+        }
+
+        if let Some(ip) = data.get("IpAddress") {
+            entry.set_client(ip);
+        }
+        for (name, key) in [
+            ("TargetDomainName", "domain"),
+            ("WorkstationName", "workstation"),
+            ("Workstation", "workstation"),
+            ("IpPort", "port"),
+            ("LogonType", "logon_type"),
+            ("AuthenticationPackageName", "auth_method"),
+            ("PackageName", "auth_method"),
+            ("Status", "status_code"),
+            ("SubStatus", "sub_status_code"),
+            ("FailureReason", "failure_reason"),
+        ] {
+            if let Some(value) = data.get(name) {
+                entry.attributes.insert(key.to_string(), value.to_string());
+            }
+        }
+
+        if let Some(user) = data.get("TargetUserName") {
+            entry.set_user(user);
+        }
+        entry.message = format!("{} ({})", description, event_id);
+        Some(entry)
+    }
+}
+
+fn child<'a, 'input>(parent: roxmltree::Node<'a, 'input>, name: &str) -> Option<roxmltree::Node<'a, 'input>> {
+    parent.children().find(|n| n.is_element() && n.tag_name().name() == name)
+}
+
+fn is_success_status(status: &str) -> bool {
+    let digits = status.trim_start_matches("0x").trim_start_matches("0X");
+    u32::from_str_radix(digits, 16).is_ok_and(|code| code == 0)
+}
@@ -1,21 +1,98 @@
+#[cfg(feature = "gui")]
 use eframe::egui;
-mod log_analyzer;
+#[cfg(feature = "gui")]
+use log_hawk::{follow, listener, log_parser};
+mod bench;
+mod cli;
+#[cfg(feature = "gui")]
 mod tab;
+#[cfg(feature = "gui")]
 use tab::Tab;
+#[cfg(feature = "gui")]
 mod log_hawk_app;
+#[cfg(feature = "gui")]
 use log_hawk_app::*;
 
-fn main() -> eframe::Result<()> {
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("--bench") => {
+            let lines = args.get(2).and_then(|n| n.parse().ok()).unwrap_or(bench::DEFAULT_BENCH_LINES);
+            if let Err(err) = bench::run(lines) {
+                eprintln!("Ошибка бенчмарка: {}", err);
+                std::process::exit(1);
+            }
+        }
+        Some("help" | "--help" | "-h") => println!("{}", cli::USAGE),
+        // `log_hawk gui [inputs...]` opens with those loaded; `-` is standard input.
+        Some("gui") => match args[2..].iter().find(|arg| arg.starts_with('-') && *arg != "-") {
+            Some(option) => usage_error(&format!("Неизвестная опция: {}", option)),
+            None => run_gui(args[2..].to_vec()),
+        },
+        // Anything else must be a report, so a typo or a pipe into a server
+        // without a display never ends up trying to open a window.
+        Some(name) => match cli::Command::parse(name) {
+            Some(command) => std::process::exit(cli::run(command, &args[2..])),
+            None if name.starts_with('-') && name != "-" => usage_error(&format!("Неизвестная опция: {}", name)),
+            None => usage_error(&format!("Неизвестная команда: {}", name)),
+        },
+        None => run_gui(Vec::new()),
+    }
+}
+
+fn usage_error(message: &str) {
+    eprintln!("{}\n\n{}", message, cli::USAGE);
+    std::process::exit(2);
+}
+
+#[cfg(not(feature = "gui"))]
+fn run_gui(_inputs: Vec<String>) {
+    eprintln!("LogHawk собран без графического интерфейса (feature `gui`)\n\n{}", cli::USAGE);
+    std::process::exit(2);
+}
+
+#[cfg(feature = "gui")]
+fn run_gui(inputs: Vec<String>) {
+    // File loading runs on tokio's blocking pool; the guard lets the UI spawn onto it.
+    let runtime = tokio::runtime::Runtime::new().expect("failed to start tokio runtime");
+    let _guard = runtime.enter();
     let options = eframe::NativeOptions::default();
-    eframe::run_native(
+    let result = eframe::run_native(
         "LogHawk",
         options,
-        Box::new(|_cc| Ok(Box::new(LogHawkApp::default()))),
-    )
+        Box::new(move |_cc| {
+            let mut app = LogHawkApp {
+                custom_formats_path: log_parser::CUSTOM_FORMATS_FILE.to_string(),
+                listen_port: listener::DEFAULT_PORT,
+                listen_udp: true,
+                listen_tcp: true,
+                ..Default::default()
+            };
+            if std::path::Path::new(&app.custom_formats_path).exists() {
+                app.load_custom_formats();
+            }
+            if !inputs.is_empty() {
+                app.load_inputs(inputs);
+            }
+            Ok(Box::new(app))
+        }),
+    );
+    if let Err(err) = result {
+        eprintln!("Не удалось открыть окно: {}", err);
+        std::process::exit(1);
+    }
 }
 
+#[cfg(feature = "gui")]
 impl eframe::App for LogHawkApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if self.poll_loading() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(100));
+        }
+        if self.poll_live() {
+            ctx.request_repaint_after(follow::POLL_INTERVAL);
+        }
+
         egui::SidePanel::left("side_panel").show(ctx, |ui| {
             ui.heading("📊 LogHawk");
 
@@ -64,9 +141,7 @@ impl eframe::App for LogHawkApp {
                 Tab::Overview => self.show_overview_tab(ui),
                 Tab::Statistics => self.show_statistics_tab(ui),
                 Tab::SuspiciousIPs => self.show_suspicious_ips_tab(ui),
-                Tab::Settings => {
-                    ui.label("⚙ Settings here");
-                },
+                Tab::Settings => self.show_settings_tab(ui),
                 Tab::Graphics => {
                     self.show_graphs_tab(ui);
                 },
@@ -0,0 +1,83 @@
+use std::cmp::Reverse;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use chrono::NaiveDate;
+use regex::Regex;
+
+const COMPRESSED_EXTENSIONS: [&str; 4] = [".gz", ".bz2", ".xz", ".zst"];
+
+static DATE_SUFFIX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(.+?)[-._](\d{4})-?(\d{2})-?(\d{2})(?:[-_]?\d{2,6})?$").unwrap()
+});
+static NUMBER_SUFFIX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(.+)\.(\d+)$").unwrap());
+
+/// Where a file sits in a logrotate series. Sorting puts the oldest first:
+/// `auth.log.3.gz`, `auth.log.2.gz`, `auth.log.1`, then `dateext` files
+/// (`auth.log-20240301`) by date, then the live `auth.log`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Rotation {
+    Numbered(Reverse<u32>),
+    Dated(NaiveDate),
+    Current,
+}
+
+/// Splits a file name into the name of its series and its rotation.
+fn rotation(file_name: &str) -> (String, Rotation) {
+    let name = COMPRESSED_EXTENSIONS
+        .iter()
+        .find_map(|ext| file_name.strip_suffix(ext))
+        .unwrap_or(file_name);
+    if let Some(caps) = NUMBER_SUFFIX.captures(name) {
+        if let Ok(number) = caps[2].parse() {
+            return (caps[1].to_string(), Rotation::Numbered(Reverse(number)));
+        }
+    }
+    if let Some(caps) = DATE_SUFFIX.captures(name) {
+        let date = NaiveDate::from_ymd_opt(caps[2].parse().unwrap_or(0), caps[3].parse().unwrap_or(0), caps[4].parse().unwrap_or(0));
+        if let Some(date) = date {
+            return (caps[1].to_string(), Rotation::Dated(date));
+        }
+    }
+    (name.to_string(), Rotation::Current)
+}
+
+/// Standard input (`-`) and anything that is not a regular file — a named
+/// pipe, `/dev/stdin`, a process substitution — can only be read once, as it
+/// arrives.
+pub fn is_stream(input: &str) -> bool {
+    input == "-" || std::fs::metadata(input).is_ok_and(|meta| !meta.is_file() && !meta.is_dir())
+}
+
+/// Turns what the user picked — files, directories (their files, not
+/// recursively) and glob patterns such as `/var/log/auth.log*` — into a list
+/// of files ordered oldest first within each logrotate series.
+pub fn expand_inputs(inputs: &[String]) -> Result<Vec<String>, String> {
+    let mut files = Vec::new();
+    for input in inputs {
+        let path = Path::new(input);
+        if path.is_dir() {
+            let entries = path.read_dir().map_err(|err| format!("Не удалось прочитать папку {}: {}", input, err))?;
+            for entry in entries {
+                let entry = entry.map_err(|err| format!("Не удалось прочитать папку {}: {}", input, err))?;
+                if entry.file_type().is_ok_and(|kind| kind.is_file()) {
+                    files.push(entry.path().display().to_string());
+                }
+            }
+        } else if input.contains(['*', '?', '[']) {
+            let paths = glob::glob(input).map_err(|err| format!("Некорректный шаблон {}: {}", input, err))?;
+            files.extend(paths.flatten().filter(|path| path.is_file()).map(|path| path.display().to_string()));
+        } else {
+            files.push(input.clone());
+        }
+    }
+
+    files.sort_by_cached_key(|file| {
+        let path = Path::new(file);
+        let file_name = path.file_name().map_or_else(|| file.clone(), |name| name.to_string_lossy().into_owned());
+        let (series, rotation) = rotation(&file_name);
+        (path.parent().map(Path::to_path_buf), series, rotation)
+    });
+    files.dedup();
+    Ok(files)
+}
@@ -0,0 +1,267 @@
+use std::sync::LazyLock;
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use regex::Regex;
+
+/// Every timestamp is normalized to UTC once parsed; zones only matter when
+/// reading zone-less input and when displaying.
+pub type Timestamp = DateTime<Utc>;
+
+static RE_ISO_DATE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?:^|\D)(\d{4})-?(\d{2})-?(\d{2})(?:\D|$)").unwrap());
+static RE_DOTTED_DATE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?:^|\D)(\d{2})\.(\d{2})\.(\d{4})(?:\D|$)").unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Zone {
+    #[default]
+    Local,
+    Utc,
+    Named(Tz),
+}
+
+impl Zone {
+    pub fn name(&self) -> String {
+        match self {
+            Zone::Local => "local".to_string(),
+            Zone::Utc => "UTC".to_string(),
+            Zone::Named(tz) => tz.name().to_string(),
+        }
+    }
+
+    /// The inverse of `name`; also accepts `utc` and `Local` in any case.
+    pub fn parse(name: &str) -> Option<Self> {
+        if name.eq_ignore_ascii_case("local") {
+            Some(Zone::Local)
+        } else if name.eq_ignore_ascii_case("utc") {
+            Some(Zone::Utc)
+        } else {
+            name.parse::<Tz>().ok().map(Zone::Named)
+        }
+    }
+
+    /// Interprets a wall clock time in this zone; in a DST fold the earlier
+    /// instant wins, in a DST gap there is no answer.
+    pub fn resolve(&self, naive: NaiveDateTime) -> Option<Timestamp> {
+        match self {
+            Zone::Local => Local.from_local_datetime(&naive).earliest().map(|ts| ts.to_utc()),
+            Zone::Utc => Some(Utc.from_utc_datetime(&naive)),
+            Zone::Named(tz) => tz.from_local_datetime(&naive).earliest().map(|ts| ts.to_utc()),
+        }
+    }
+
+    pub fn format(&self, ts: &Timestamp, format: &str) -> String {
+        match self {
+            Zone::Local => ts.with_timezone(&Local).format(format).to_string(),
+            Zone::Utc => ts.format(format).to_string(),
+            Zone::Named(tz) => ts.with_timezone(tz).format(format).to_string(),
+        }
+    }
+}
+
+/// Finds a calendar date (`yyyy-mm-dd`, `yyyymmdd` or `dd.mm.yyyy`) in free text.
+pub fn find_date(text: &str) -> Option<NaiveDate> {
+    RE_ISO_DATE
+        .captures_iter(text)
+        .find_map(|caps| NaiveDate::from_ymd_opt(caps[1].parse().ok()?, caps[2].parse().ok()?, caps[3].parse().ok()?))
+        .or_else(|| {
+            RE_DOTTED_DATE
+                .captures_iter(text)
+                .find_map(|caps| NaiveDate::from_ymd_opt(caps[3].parse().ok()?, caps[2].parse().ok()?, caps[1].parse().ok()?))
+        })
+}
+
+/// Turns the timestamps of one input into UTC instants.
+///
+/// Parsers hand it whatever the line carries. Missing pieces are filled from
+/// what the resolver has seen so far: the date from the file name or from a
+/// header/banner line, the date of the previous record, and finally today.
+/// Times that jump backwards by more than an hour are taken as a midnight
+/// rollover, and syslog dates that jump back by more than a month as a new year.
+/// A syslog date read with nothing to pin its year is never put in the future:
+/// `Dec 31` read in January belongs to the year before.
+#[derive(Debug, Clone)]
+pub struct TimeResolver {
+    zone: Zone,
+    date: Option<NaiveDate>,
+    last: Option<NaiveDateTime>,
+    /// Bumped whenever the date context is consulted or moved, so chunked
+    /// parsing can tell which records depended on it.
+    updates: usize,
+}
+
+impl TimeResolver {
+    pub fn new(zone: Zone) -> Self {
+        Self { zone, date: None, last: None, updates: 0 }
+    }
+
+    /// A resolver for parsing ahead from this point without knowing the last
+    /// record: it keeps the current date but forgets the previous time.
+    pub fn fork(&self) -> Self {
+        Self { date: self.last.map(|last| last.date()).or(self.date), last: None, ..self.clone() }
+    }
+
+    pub fn updates(&self) -> usize {
+        self.updates
+    }
+
+    /// Whether both resolvers will resolve the following records the same way.
+    /// The base date only matters until a record has been resolved.
+    pub fn same_context(&self, other: &Self) -> bool {
+        self.zone == other.zone && self.last == other.last && (self.last.is_some() || self.date == other.date)
+    }
+
+    pub fn for_file(zone: Zone, path: &str) -> Self {
+        let name = std::path::Path::new(path)
+            .file_name()
+            .map_or_else(|| path.to_string(), |name| name.to_string_lossy().into_owned());
+        Self { date: find_date(&name), ..Self::new(zone) }
+    }
+
+    /// Called for lines that are not records, so header or banner lines such
+    /// as `# Date: 2024-01-05` move the current date.
+    pub fn observe_header(&mut self, line: &str) {
+        if let Some(date) = find_date(line) {
+            self.date = Some(date);
+            self.last = None;
+            self.updates += 1;
+        }
+    }
+
+    fn current_date(&self) -> NaiveDate {
+        self.last
+            .map(|last| last.date())
+            .or(self.date)
+            .unwrap_or_else(|| Local::now().date_naive())
+    }
+
+    fn local(&mut self, naive: NaiveDateTime) -> Option<Timestamp> {
+        self.last = Some(naive);
+        self.updates += 1;
+        self.zone.resolve(naive)
+    }
+
+    /// `HH:MM:SS[.fff]` with no date.
+    pub fn clock(&mut self, value: &str) -> Option<Timestamp> {
+        let time = NaiveTime::parse_from_str(value.trim(), "%H:%M:%S%.f").ok()?;
+        let mut naive = self.current_date().and_time(time);
+        if self.last.is_some_and(|last| naive + Duration::hours(1) < last) {
+            naive += Duration::days(1);
+        }
+        self.local(naive)
+    }
+
+    /// BSD syslog `Mmm dd HH:MM:SS`, which carries no year.
+    pub fn syslog(&mut self, value: &str) -> Option<Timestamp> {
+        self.syslog_at(value, Local::now().naive_local())
+    }
+
+    fn syslog_at(&mut self, value: &str, now: NaiveDateTime) -> Option<Timestamp> {
+        let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+        let pinned = self.last.map(|last| last.date()).or(self.date);
+        let year = pinned.unwrap_or(now.date()).year();
+        let parse = |year: i32| NaiveDateTime::parse_from_str(&format!("{} {}", year, value), "%Y %b %d %H:%M:%S%.f").ok();
+        let mut naive = parse(year)?;
+        if self.last.is_some_and(|last| naive + Duration::days(31) < last) {
+            naive = parse(year + 1)?;
+        } else if pinned.is_none() && naive > now + Duration::days(1) {
+            naive = parse(year - 1).unwrap_or(naive);
+        }
+        self.local(naive)
+    }
+
+    /// Apache/Nginx `dd/Mmm/yyyy:HH:MM:SS +zzzz`.
+    pub fn access(&mut self, value: &str) -> Option<Timestamp> {
+        DateTime::parse_from_str(value, "%d/%b/%Y:%H:%M:%S %z").ok().map(|ts| ts.to_utc())
+    }
+
+    pub fn with_format(&mut self, value: &str, format: &str) -> Option<Timestamp> {
+        if let Ok(ts) = DateTime::parse_from_str(value, format) {
+            return Some(ts.to_utc());
+        }
+        if let Ok(naive) = NaiveDateTime::parse_from_str(value, format) {
+            return self.local(naive);
+        }
+        if let Ok(time) = NaiveTime::parse_from_str(value, format) {
+            return self.clock(&time.format("%H:%M:%S%.f").to_string());
+        }
+        NaiveDate::parse_from_str(value, format)
+            .ok()
+            .and_then(|date| self.local(date.and_time(NaiveTime::MIN)))
+    }
+
+    /// Best effort parsing for formats that do not pin the timestamp layout:
+    /// RFC 3339, common `yyyy-mm-dd HH:MM:SS` variants, Unix epoch seconds or
+    /// milliseconds, and the shorter syslog and clock forms.
+    pub fn any(&mut self, value: &str) -> Option<Timestamp> {
+        let value = value.trim();
+        if value.is_empty() {
+            return None;
+        }
+        if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+            return Some(ts.to_utc());
+        }
+        if let Ok(ts) = DateTime::parse_from_rfc2822(value) {
+            return Some(ts.to_utc());
+        }
+        for format in ["%Y-%m-%d %H:%M:%S%.f%:z", "%Y-%m-%d %H:%M:%S%.f %z", "%Y-%m-%dT%H:%M:%S%.f%z"] {
+            if let Ok(ts) = DateTime::parse_from_str(value, format) {
+                return Some(ts.to_utc());
+            }
+        }
+        for format in ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%Y/%m/%d %H:%M:%S%.f", "%d.%m.%Y %H:%M:%S%.f"] {
+            if let Ok(naive) = NaiveDateTime::parse_from_str(value, format) {
+                return self.local(naive);
+            }
+        }
+        if let Ok(epoch) = value.parse::<i64>() {
+            return if epoch.abs() >= 100_000_000_000 {
+                DateTime::from_timestamp_millis(epoch)
+            } else {
+                DateTime::from_timestamp(epoch, 0)
+            };
+        }
+        self.access(value)
+            .or_else(|| self.syslog(value))
+            .or_else(|| self.clock(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn syslog_date_without_context_is_not_in_the_future() {
+        let now = at("2025-01-10 08:00:00");
+
+        let mut time = TimeResolver::new(Zone::Utc);
+        let ts = time.syslog_at("Dec 31 23:59:59", now).unwrap();
+        assert_eq!(ts.naive_utc(), at("2024-12-31 23:59:59"));
+        // The next record rolls over into the new year as usual.
+        let ts = time.syslog_at("Jan  1 00:00:01", now).unwrap();
+        assert_eq!(ts.naive_utc(), at("2025-01-01 00:00:01"));
+
+        // Within a day of now counts as the current year (clock and zone skew).
+        let mut time = TimeResolver::new(Zone::Utc);
+        let ts = time.syslog_at("Jan 11 07:00:00", now).unwrap();
+        assert_eq!(ts.naive_utc(), at("2025-01-11 07:00:00"));
+    }
+
+    #[test]
+    fn syslog_year_pinned_by_file_name_or_header() {
+        let now = at("2025-01-10 08:00:00");
+
+        let mut time = TimeResolver::for_file(Zone::Utc, "/var/log/auth.log-20251231");
+        let ts = time.syslog_at("Dec 31 23:59:59", now).unwrap();
+        assert_eq!(ts.naive_utc(), at("2025-12-31 23:59:59"));
+
+        let mut time = TimeResolver::new(Zone::Utc);
+        time.observe_header("# Date: 2025-06-01");
+        let ts = time.syslog_at("Dec 31 23:59:59", now).unwrap();
+        assert_eq!(ts.naive_utc(), at("2025-12-31 23:59:59"));
+    }
+}